@@ -1,57 +1,192 @@
 use rand::Rng;
+use serde::Serialize;
+use crate::neighbourhood::Lattice;
+use crate::profile::AgentProfile;
+
+/// Champs et couplages utilisés par le choix probabiliste :
+/// p(i, j) ∝ exp(-k_s * S(i, j)) * exp(k_d * D(i, j)) * exp(k_i * I(i, j)),
+/// où I est l'alignement du pas avec le déplacement précédent
+pub struct FieldCoupling<'a> {
+    pub static_field: &'a [Vec<f32>],
+    pub dynamic_field: &'a [Vec<f32>],
+    pub k_s: f32,
+    pub k_d: f32,
+    pub k_i: f32,
+}
+
+/// Encombrement d'un agent en cellules ; la position de l'agent est la
+/// cellule en haut à gauche de son encombrement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Footprint {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Footprint {
+    /// Piéton ordinaire : une seule cellule
+    pub const SINGLE: Footprint = Footprint { width: 1, height: 1 };
+
+    pub fn is_single(self) -> bool {
+        self == Footprint::SINGLE
+    }
+
+    /// Cellules couvertes quand l'agent est en (x, y)
+    pub fn cells(self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        (y..y + self.height).flat_map(move |cy| (x..x + self.width).map(move |cx| (cx, cy)))
+    }
+}
+
+impl Default for Footprint {
+    fn default() -> Self {
+        Footprint::SINGLE
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Agent {
+    /// Identifiant stable, jamais réutilisé au cours d'une simulation
+    pub id: usize,
     pub x: usize,
     pub y: usize,
     pub phase_offset: f32,
+    /// Identifiant de la sortie visée
+    pub target_exit: usize,
+    /// Étape d'apparition dans la salle
+    pub spawn_step: usize,
+    /// Étapes où l'agent a tenté de bouger sans changer de cellule (bloqué
+    /// ou conflit perdu) ; les tours sautés d'un agent lent n'en font pas partie
+    pub waiting_steps: usize,
+    /// Distance parcourue, en cellules (longueur euclidienne de chaque pas)
+    pub path_length: f32,
+    pub profile: AgentProfile,
+    /// Probabilité de tenter un déplacement à chaque étape (vitesse relative)
+    pub move_probability: f32,
+    pub footprint: Footprint,
+    /// Dernier déplacement effectué, de centre à centre (en largeurs de
+    /// cellule), conservé pendant les attentes ; (0, 0) tant que l'agent n'a pas bougé
+    pub last_move: (f32, f32),
+}
+
+/// Bilan individuel d'un agent, sorti ou encore dans la salle
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentRecord {
+    pub id: usize,
+    pub profile: AgentProfile,
+    pub spawn_step: usize,
+    /// Étape de sortie (`None` si l'agent est encore dans la salle)
+    pub exit_step: Option<usize>,
+    /// Identifiant de la sortie empruntée
+    pub exit: Option<usize>,
+    pub waiting_steps: usize,
+    pub path_length: f32,
+    /// Dernière position connue (cellule de sortie pour un agent évacué)
+    pub x: usize,
+    pub y: usize,
+    pub footprint: Footprint,
+}
+
+impl AgentRecord {
+    /// Cellules couvertes à la dernière position connue
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.footprint.cells(self.x, self.y)
+    }
 }
 
 impl Agent {
-    pub fn new(x: usize, y: usize, _id: usize) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn new(x: usize, y: usize, id: usize, rng: &mut impl Rng) -> Self {
         Agent { 
+            id,
             x, 
             y,
             phase_offset: rng.gen::<f32>(),
+            target_exit: 0,
+            spawn_step: 0,
+            waiting_steps: 0,
+            path_length: 0.0,
+            profile: AgentProfile::Adult,
+            move_probability: 1.0,
+            footprint: Footprint::SINGLE,
+            last_move: (0.0, 0.0),
+        }
+    }
+
+    /// Cellules occupées par l'agent
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.footprint.cells(self.x, self.y)
+    }
+
+    /// Bilan de l'agent ; `exit` donne l'étape et la sortie s'il est évacué
+    pub fn record(&self, exit: Option<(usize, usize)>) -> AgentRecord {
+        AgentRecord {
+            id: self.id,
+            profile: self.profile,
+            spawn_step: self.spawn_step,
+            exit_step: exit.map(|(step, _)| step),
+            exit: exit.map(|(_, exit)| exit),
+            waiting_steps: self.waiting_steps,
+            path_length: self.path_length,
+            x: self.x,
+            y: self.y,
+            footprint: self.footprint,
         }
     }
     
-    /// Voisinage de Moore - Priorité aux directions cardinales
-    pub fn get_neighbors(&self) -> Vec<(usize, usize)> {
-        let mut neighbors = Vec::new();
-        
-        let directions = [
-            (0, -1),   // Haut
-            (1,  0),   // Droite
-            (0,  1),   // Bas
-            (-1, 0),   // Gauche
-            (1, -1),   // Haut-Droite
-            (1,  1),   // Bas-Droite
-            (-1, 1),   // Bas-Gauche
-            (-1,-1),   // Haut-Gauche
-        ];
-        
-        for (dx, dy) in directions.iter() {
-            let new_x = self.x as i32 + dx;
-            let new_y = self.y as i32 + dy;
-            
-            if new_x >= 0 && new_y >= 0 {
-                neighbors.push((new_x as usize, new_y as usize));
-            }
+    /// Cellules du voisinage - Priorité aux directions cardinales
+    pub fn get_neighbors(&self, lattice: Lattice) -> Vec<(usize, usize)> {
+        lattice.neighbours(self.x, self.y)
+    }
+
+    /// Vrai si la cellule voisine (x, y) est praticable, ainsi que les
+    /// cellules survolées pour l'atteindre (voisinage étendu)
+    fn can_reach(&self, x: usize, y: usize, lattice: Lattice, is_walkable: &impl Fn(usize, usize) -> bool) -> bool {
+        is_walkable(x, y)
+            && lattice
+                .crossed_cells((self.x, self.y), (x, y))
+                .into_iter()
+                .all(|(cx, cy)| is_walkable(cx, cy))
+    }
+
+    /// Déplacement de centre à centre vers (x, y)
+    fn displacement(&self, x: usize, y: usize, lattice: Lattice) -> (f32, f32) {
+        let (x0, y0) = lattice.centre(self.x, self.y);
+        let (x1, y1) = lattice.centre(x, y);
+        (x1 - x0, y1 - y0)
+    }
+    
+    /// Alignement du pas vers (x, y) avec le déplacement précédent : cosinus
+    /// de l'angle entre les deux (1 tout droit, -1 demi-tour, 0 sans mémoire)
+    pub fn alignment(&self, x: usize, y: usize, lattice: Lattice) -> f32 {
+        let (lx, ly) = self.last_move;
+        if (lx, ly) == (0.0, 0.0) || (x, y) == (self.x, self.y) {
+            return 0.0;
         }
-        
-        neighbors
+        let (dx, dy) = self.displacement(x, y, lattice);
+        (dx * lx + dy * ly) / dx.hypot(dy) / lx.hypot(ly)
+    }
+
+    /// Place l'agent en (x, y) en retenant le déplacement et sa longueur s'il a bougé
+    pub fn move_to(&mut self, x: usize, y: usize, lattice: Lattice) {
+        if (x, y) != (self.x, self.y) {
+            self.last_move = self.displacement(x, y, lattice);
+            self.path_length += lattice.step_length((self.x, self.y), (x, y));
+        }
+        self.x = x;
+        self.y = y;
     }
     
     /// Choisit la meilleure position basée sur le gradient du champ de potentiel
-    /// Avec un petit bruit pour éviter les mouvements trop synchronisés
+    /// Avec un petit bruit pour éviter les mouvements trop synchronisés.
+    /// `k_i` (inertie) réduit le coût des pas qui prolongent le déplacement précédent
+    #[allow(clippy::too_many_arguments)]
     pub fn choose_next_position(
         &self,
         floor_field: &[Vec<f32>],
+        k_i: f32,
+        lattice: Lattice,
         grid_width: usize,
         grid_height: usize,
         is_walkable: impl Fn(usize, usize) -> bool,
+        rng: &mut impl Rng,
     ) -> Option<(usize, usize)> {
         let current_dist = floor_field[self.y][self.x];
         
@@ -59,14 +194,19 @@ impl Agent {
             return None;
         }
         
-        let neighbors = self.get_neighbors();
+        let neighbors = self.get_neighbors(lattice);
         let mut candidates = Vec::new();
         let mut best_dist = current_dist;
         
         // Chercher les voisins avec plus faible pottentiel
         for (nx, ny) in neighbors {
-            if nx < grid_width && ny < grid_height && is_walkable(nx, ny) {
-                let distance = floor_field[ny][nx];
+            if nx < grid_width && ny < grid_height && self.can_reach(nx, ny, lattice, &is_walkable) {
+                let mut distance = floor_field[ny][nx];
+                // L'inertie départage les pas qui rapprochent de la sortie
+                // sans jamais rendre attractif un pas qui en éloigne
+                if distance < current_dist {
+                    distance -= k_i * self.alignment(nx, ny, lattice);
+                }
                 
                 if distance < best_dist {
                     best_dist = distance;
@@ -83,44 +223,42 @@ impl Agent {
         }
         
         // Choisir parmi les candidats avec un petit biais aléatoire
-        let mut rng = rand::thread_rng();
         let noise: f32 = rng.gen::<f32>() * 0.3; // Bruit ajouté
         
-        candidates.sort_by(|a, b| {
-            let score_a = a.2 + noise * (rng.gen::<f32>() - 0.5);
-            let score_b = b.2 + noise * (rng.gen::<f32>() - 0.5);
-            score_a.partial_cmp(&score_b).unwrap()
-        });
-        
-        Some((candidates[0].0, candidates[0].1))
+        // Un tirage par candidat (un comparateur aléatoire ne définit pas un ordre)
+        candidates
+            .iter()
+            .map(|&(x, y, distance)| ((x, y), distance + noise * (rng.gen::<f32>() - 0.5)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(pos, _)| pos)
     }
     
     /// Version avec probabilités pour un comportement plus naturel (optionnel)
     pub fn choose_next_position_probabilistic(
         &self,
-        floor_field: &[Vec<f32>],
+        fields: &FieldCoupling,
+        lattice: Lattice,
         grid_width: usize,
         grid_height: usize,
         is_walkable: impl Fn(usize, usize) -> bool,
-        k_s: f32,
+        rng: &mut impl Rng,
     ) -> Option<(usize, usize)> {
-        let neighbors = self.get_neighbors();
+        let neighbors = self.get_neighbors(lattice);
         let mut valid_moves = Vec::new();
-        let mut probabilities = Vec::new();
-        let mut total_prob = 0.0;
+        let mut scores = Vec::new();
         
         for (nx, ny) in neighbors {
-            if nx < grid_width && ny < grid_height && is_walkable(nx, ny) {
-                let distance = floor_field[ny][nx];
+            if nx < grid_width && ny < grid_height && self.can_reach(nx, ny, lattice, &is_walkable) {
+                let distance = fields.static_field[ny][nx];
                 
                 // Skip if distance is infinite (unreachable)
                 if distance.is_finite() {
-                    // Probabilité inversement proportionnelle à la distance
-                    // Plus la distance est petite, plus la probabilité est grande
-                    let prob = (-k_s * distance).exp();
+                    // Plus la distance est petite et la trace forte, plus la probabilité est grande
+                    let score = -fields.k_s * distance
+                        + fields.k_d * fields.dynamic_field[ny][nx]
+                        + fields.k_i * self.alignment(nx, ny, lattice);
                     valid_moves.push((nx, ny));
-                    probabilities.push(prob);
-                    total_prob += prob;
+                    scores.push(score);
                 }
             }
         }
@@ -130,8 +268,12 @@ impl Agent {
             return None;
         }
         
+        // Scores ramenés au meilleur candidat pour éviter que exp() ne s'annule loin de la sortie
+        let best_score = scores.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let probabilities: Vec<f32> = scores.iter().map(|s| (s - best_score).exp()).collect();
+        let total_prob: f32 = probabilities.iter().sum();
+        
         // Gestion des probabilités et du choix aléatoires
-        let mut rng = rand::thread_rng();
         let mut roll: f32 = rng.gen::<f32>() * total_prob;
         
         for (i, &prob) in probabilities.iter().enumerate() {
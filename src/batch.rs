@@ -0,0 +1,219 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use serde::Serialize;
+use crate::scenario::{Scenario, ScenarioError};
+use crate::simulation::{RunSummary, Simulation};
+
+/// Statistiques descriptives d'un échantillon
+#[derive(Debug, Clone, Serialize)]
+pub struct Statistics {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p95: f64,
+    /// Intervalle de confiance à 95 % sur la moyenne (approximation normale)
+    pub ci95: (f64, f64),
+}
+
+impl Statistics {
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let n = samples.len() as f64;
+        if samples.is_empty() {
+            return Statistics {
+                mean: f64::NAN,
+                std_dev: f64::NAN,
+                min: f64::NAN,
+                max: f64::NAN,
+                p5: f64::NAN,
+                p25: f64::NAN,
+                median: f64::NAN,
+                p75: f64::NAN,
+                p95: f64::NAN,
+                ci95: (f64::NAN, f64::NAN),
+            };
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let mean = sorted.iter().sum::<f64>() / n;
+        // Écart-type corrigé (n - 1)
+        let std_dev = if sorted.len() > 1 {
+            (sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        let half_width = 1.96 * std_dev / n.sqrt();
+
+        Statistics {
+            mean,
+            std_dev,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p5: percentile(&sorted, 5.0),
+            p25: percentile(&sorted, 25.0),
+            median: percentile(&sorted, 50.0),
+            p75: percentile(&sorted, 75.0),
+            p95: percentile(&sorted, 95.0),
+            ci95: (mean - half_width, mean + half_width),
+        }
+    }
+}
+
+/// Percentile par interpolation linéaire sur un échantillon trié
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let high = rank.ceil() as usize;
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitStatistics {
+    pub name: String,
+    pub evacuated: Statistics,
+}
+
+/// Résultat de N réplications indépendantes d'un même scénario
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub replications: usize,
+    pub base_seed: u64,
+    /// Réplications arrêtées avant la sortie de tous les agents
+    pub incomplete: usize,
+    /// Durée d'évacuation des seules réplications terminées (les réplications
+    /// coupées par `max_steps` biaiseraient la moyenne vers la limite)
+    pub steps: Statistics,
+    pub evacuated: Statistics,
+    pub exits: Vec<ExitStatistics>,
+    pub runs: Vec<RunSummary>,
+}
+
+/// Exécute `replications` simulations du scénario, la réplication `i` utilisant
+/// la graine `base_seed + i`, réparties sur `threads` fils d'exécution.
+/// Le résultat ne dépend pas du nombre de fils.
+pub fn run_batch(
+    scenario: &Scenario,
+    replications: usize,
+    threads: usize,
+    base_seed: u64,
+) -> Result<BatchReport, ScenarioError> {
+    let runs = run_replications(scenario, replications, threads, base_seed)?;
+    Ok(BatchReport::from_runs(runs, base_seed))
+}
+
+/// Bilans individuels des réplications, dans l'ordre des graines
+pub fn run_replications(
+    scenario: &Scenario,
+    replications: usize,
+    threads: usize,
+    base_seed: u64,
+) -> Result<Vec<RunSummary>, ScenarioError> {
+    // Le plan n'est lu qu'une fois ; les erreurs de scénario remontent avant de lancer les fils
+    let layout = scenario.build_layout()?;
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<RunSummary, ScenarioError>>>> =
+        Mutex::new((0..replications).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, replications.max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= replications {
+                    break;
+                }
+                let seed = base_seed.wrapping_add(i as u64);
+                let summary = Simulation::from_layout(scenario, layout.clone(), seed)
+                    .map(|mut simulation| simulation.run());
+                results.lock().unwrap()[i] = Some(summary);
+            });
+        }
+    });
+
+    results.into_inner().unwrap().into_iter().flatten().collect()
+}
+
+impl BatchReport {
+    pub fn from_runs(runs: Vec<RunSummary>, base_seed: u64) -> Self {
+        let steps: Vec<f64> = runs.iter().filter(|r| r.completed).map(|r| r.steps as f64).collect();
+        let evacuated: Vec<f64> = runs.iter().map(|r| r.evacuated as f64).collect();
+
+        let exits = runs
+            .first()
+            .map(|first| {
+                first
+                    .exits
+                    .iter()
+                    .enumerate()
+                    .map(|(e, exit)| {
+                        let counts: Vec<f64> = runs.iter().map(|r| r.exits[e].evacuated as f64).collect();
+                        ExitStatistics {
+                            name: exit.name.clone(),
+                            evacuated: Statistics::from_samples(&counts),
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        BatchReport {
+            replications: runs.len(),
+            base_seed,
+            incomplete: runs.iter().filter(|r| !r.completed).count(),
+            steps: Statistics::from_samples(&steps),
+            evacuated: Statistics::from_samples(&evacuated),
+            exits,
+            runs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn percentile_interpolates_between_ranks() {
+        let sorted = [10.0, 20.0, 30.0, 40.0, 50.0];
+        assert!(close(percentile(&sorted, 0.0), 10.0));
+        assert!(close(percentile(&sorted, 50.0), 30.0));
+        assert!(close(percentile(&sorted, 5.0), 12.0));
+        assert!(close(percentile(&sorted, 95.0), 48.0));
+        assert!(close(percentile(&sorted, 100.0), 50.0));
+        assert!(percentile(&[], 50.0).is_nan());
+    }
+
+    #[test]
+    fn statistics_of_a_known_sample() {
+        let stats = Statistics::from_samples(&[5.0, 1.0, 4.0, 2.0, 3.0]);
+        assert!(close(stats.mean, 3.0));
+        assert!(close(stats.std_dev, 2.5f64.sqrt()));
+        assert_eq!((stats.min, stats.max), (1.0, 5.0));
+        assert!(close(stats.p25, 2.0));
+        assert!(close(stats.median, 3.0));
+        assert!(close(stats.p75, 4.0));
+        let half_width = 1.96 * 2.5f64.sqrt() / 5f64.sqrt();
+        assert!(close(stats.ci95.0, 3.0 - half_width));
+        assert!(close(stats.ci95.1, 3.0 + half_width));
+    }
+
+    #[test]
+    fn single_sample_has_no_spread() {
+        let stats = Statistics::from_samples(&[7.0]);
+        assert_eq!(stats.std_dev, 0.0);
+        assert_eq!(stats.ci95, (7.0, 7.0));
+    }
+}
@@ -0,0 +1,12 @@
+//! Exécutable sans interface graphique :
+//! `cargo run --no-default-features --bin evacuation-cli -- [run|batch] [options]`
+
+use automates_evacuation::cli;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = cli::run(&args) {
+        eprintln!("Erreur : {}", err);
+        std::process::exit(2);
+    }
+}
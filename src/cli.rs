@@ -0,0 +1,482 @@
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::thread;
+use crate::batch::{run_batch, BatchReport, Statistics};
+use crate::grid::{ObstaclePattern, Topology};
+use crate::heatmap::Heatmaps;
+use crate::measurement::MeasurementRecorder;
+use crate::neighbourhood::{Neighbourhood, RADIUS_ERROR};
+use crate::recorder::{write_agent_records_csv, Recorder, TrajectoryRecorder};
+use crate::scenario::{GridSource, Population, Scenario, StopConditions};
+use crate::simulation::{RunSummary, Simulation};
+use crate::sweep::{run_sweep, write_csv, SweepSpec};
+
+/// Limite d'étapes par défaut quand ni le scénario ni la ligne de commande n'en donnent
+const DEFAULT_MAX_STEPS: usize = 10_000;
+
+/// Nombre de réplications par défaut du mode `batch`
+const DEFAULT_REPLICATIONS: usize = 30;
+
+pub const HEADLESS_USAGE: &str = "\
+Usage : evacuation-cli [run|batch|sweep] [options]
+        automates-evacuation headless [run|batch|sweep] [options]
+
+  run (par défaut)       une simulation, bilan final
+  batch                  réplications indépendantes, statistiques agrégées
+  sweep                  balayage de paramètres, CSV d'une ligne par simulation
+
+  --scenario <fichier>   scénario .json ou .toml
+  --pattern <nom>        salle prédéfinie (empty, single, rooms, exit_obstacle,
+                         multi_obstacles, labyrinth, two_exits_adjacent, two_exits_far)
+  --map <fichier>        plan ASCII
+  --width <n>            largeur de la salle prédéfinie (60)
+  --height <n>           hauteur de la salle prédéfinie (40)
+  --agents <n>           nombre d'agents placés au hasard
+  --k-s <x>              couplage au champ statique
+  --k-i <x>              inertie (persistance de la direction)
+  --probabilistic        choix de déplacement probabiliste
+  --neighbourhood <nom>  voisinage : von_neumann, moore (par défaut) ou extended:<rayon>
+  --topology <nom>       pavage : square (par défaut) ou hexagonal
+  --seed <n>             graine aléatoire
+  --max-steps <n>        nombre maximal d'étapes (10000 par défaut)
+  --format <text|json>   format du bilan (text par défaut)
+
+Options de run :
+  --timeseries <fichier> série temporelle par étape (.csv, ou .json)
+  --trajectories <fichier>  positions de chaque agent à chaque étape (CSV id,step,x,y,width,height)
+  --agent-records <fichier> bilan par agent : apparition, sortie, attente, distance (CSV)
+  --heatmaps <fichier>   cartes par cellule : occupation, passages, densité max,
+                         attente moyenne (.json, ou un CSV par carte sinon)
+  --measurements <fichier>  points du diagramme fondamental pour les zones et
+                         lignes de la section [measurement] du scénario (.csv ou .json)
+
+Options de batch :
+  --replications <n>     nombre de réplications (30 par défaut)
+  --threads <n>          fils d'exécution (tous les cœurs par défaut)
+                         la réplication i utilise la graine --seed + i
+
+Options de sweep (plus --replications et --threads) :
+  --agents <liste>       ex. 100,200,400 ou 100:500:100 (début:fin:pas)
+  --k-s <liste>          ex. 0.5:4:0.5
+  --friction <liste>     friction mu (impose la politique de conflit friction)
+  --pattern <liste|all>  salles prédéfinies, ex. empty,rooms ou all
+  --movement <liste>     greedy, probabilistic ou greedy,probabilistic
+  --neighbourhood <liste>
+                         voisinages, ex. von_neumann,moore,extended:2
+  --topology <liste>     pavages, ex. square,hexagonal
+  --output <fichier>     CSV de sortie (sortie standard par défaut)
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Options communes aux modes sans fenêtre
+pub struct RunOptions {
+    pub scenario: Scenario,
+    pub format: OutputFormat,
+}
+
+impl RunOptions {
+    /// Lit les options : un scénario (`--scenario`) éventuellement surchargé
+    /// par les autres options, ou un scénario construit à partir d'elles
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut scenario = Scenario {
+            name: None,
+            grid: GridSource::Pattern {
+                width: 60,
+                height: 40,
+                pattern: ObstaclePattern::Empty,
+            },
+            topology: Topology::Square,
+            exits: Vec::new(),
+            population: Population { agents: 200, ..Population::default() },
+            sources: Vec::new(),
+            model: Default::default(),
+            seed: None,
+            stop: StopConditions::default(),
+            measurement: Default::default(),
+        };
+
+        // Le scénario est lu en premier pour que les autres options le surchargent
+        if let Some(path) = option_value(args, "--scenario") {
+            scenario = Scenario::load(path).map_err(|e| format!("{} : {}", path, e))?;
+        }
+
+        let mut format = OutputFormat::Text;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = || iter.next().map(String::as_str).ok_or(format!("{} attend une valeur", arg));
+            match arg.as_str() {
+                "--scenario" => {
+                    value()?;
+                }
+                "--pattern" => {
+                    let pattern = parse_enum(value()?)?;
+                    let (width, height) = match scenario.grid {
+                        GridSource::Pattern { width, height, .. } => (width, height),
+                        _ => (60, 40),
+                    };
+                    scenario.grid = GridSource::Pattern { width, height, pattern };
+                }
+                "--map" => scenario.grid = GridSource::Ascii { path: value()?.into() },
+                "--width" | "--height" => {
+                    let size = parse_number(arg, value()?)?;
+                    match &mut scenario.grid {
+                        GridSource::Pattern { width, .. } if arg == "--width" => *width = size,
+                        GridSource::Pattern { height, .. } => *height = size,
+                        _ => return Err(format!("{} ne s'applique qu'aux salles prédéfinies", arg)),
+                    }
+                }
+                "--agents" => scenario.population.agents = parse_number(arg, value()?)?,
+                "--k-s" => scenario.model.k_s = parse_number(arg, value()?)?,
+                "--k-i" => scenario.model.k_i = parse_number(arg, value()?)?,
+                "--probabilistic" => scenario.model.use_probabilistic = true,
+                "--neighbourhood" => scenario.model.neighbourhood = parse_neighbourhood(value()?)?,
+                "--topology" => scenario.topology = parse_enum(value()?)?,
+                "--seed" => scenario.seed = Some(parse_number(arg, value()?)?),
+                "--max-steps" => scenario.stop.max_steps = Some(parse_number(arg, value()?)?),
+                "--format" => {
+                    format = match value()? {
+                        "text" => OutputFormat::Text,
+                        "json" => OutputFormat::Json,
+                        other => return Err(format!("format inconnu : {}", other)),
+                    }
+                }
+                other => return Err(format!("option inconnue : {}", other)),
+            }
+        }
+
+        if scenario.stop.max_steps.is_none() {
+            scenario.stop.max_steps = Some(DEFAULT_MAX_STEPS);
+        }
+
+        Ok(RunOptions { scenario, format })
+    }
+}
+
+/// Point d'entrée sans fenêtre : le premier argument choisit la commande
+pub fn run(args: &[String]) -> Result<(), String> {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print!("{}", HEADLESS_USAGE);
+        return Ok(());
+    }
+
+    match args.first().map(String::as_str) {
+        Some("run") => run_headless(&args[1..]),
+        Some("batch") => run_batch_command(&args[1..]),
+        Some("sweep") => run_sweep_command(&args[1..]),
+        _ => run_headless(args),
+    }
+}
+
+/// Une simulation jusqu'à la fin de l'évacuation ou la limite d'étapes
+pub fn run_headless(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let timeseries = take_option(&mut args, "--timeseries")?;
+    let trajectories = take_option(&mut args, "--trajectories")?;
+    let agent_records = take_option(&mut args, "--agent-records")?;
+    let heatmaps_path = take_option(&mut args, "--heatmaps")?;
+    let measurements = take_option(&mut args, "--measurements")?;
+
+    let options = RunOptions::parse(&args)?;
+    if measurements.is_some() && options.scenario.measurement.is_empty() {
+        return Err("--measurements : le scénario ne définit aucune zone ni ligne de mesure".to_string());
+    }
+    let mut simulation = Simulation::from_scenario(&options.scenario).map_err(|e| e.to_string())?;
+
+    let mut series = timeseries.as_ref().map(|_| Recorder::new(&simulation));
+    let mut paths = trajectories.as_ref().map(|_| TrajectoryRecorder::new(&simulation));
+    let mut heatmaps = heatmaps_path.as_ref().map(|_| Heatmaps::new(&simulation));
+    let mut measurer = measurements
+        .as_ref()
+        .map(|_| MeasurementRecorder::new(options.scenario.measurement.clone(), &simulation));
+    while !simulation.is_finished() {
+        simulation.step();
+        if let Some(series) = &mut series {
+            series.record(&simulation);
+        }
+        if let Some(paths) = &mut paths {
+            paths.record(&simulation);
+        }
+        if let Some(heatmaps) = &mut heatmaps {
+            heatmaps.record(&simulation);
+        }
+        if let Some(measurer) = &mut measurer {
+            measurer.record(&simulation);
+        }
+    }
+    let summary = simulation.summary();
+
+    if let (Some(path), Some(series)) = (&timeseries, &series) {
+        series.save(path).map_err(|e| format!("{} : {}", path, e))?;
+    }
+    if let (Some(path), Some(paths)) = (&trajectories, &paths) {
+        paths.save(path).map_err(|e| format!("{} : {}", path, e))?;
+    }
+    if let (Some(path), Some(heatmaps)) = (&heatmaps_path, &heatmaps) {
+        heatmaps.save(path).map_err(|e| format!("{} : {}", path, e))?;
+    }
+    if let (Some(path), Some(measurer)) = (&measurements, &mut measurer) {
+        measurer.finish();
+        measurer.save(path).map_err(|e| format!("{} : {}", path, e))?;
+    }
+    if let Some(path) = &agent_records {
+        File::create(path)
+            .and_then(|file| write_agent_records_csv(&simulation.agent_records(), BufWriter::new(file)))
+            .map_err(|e| format!("{} : {}", path, e))?;
+    }
+
+    match options.format {
+        OutputFormat::Text => print!("{}", format_summary(&summary)),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?
+        ),
+    }
+    Ok(())
+}
+
+/// N réplications du scénario, statistiques sur les étapes d'évacuation et les sorties
+pub fn run_batch_command(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let (replications, threads) = take_batch_options(&mut args)?;
+
+    let options = RunOptions::parse(&args)?;
+    let base_seed = options.scenario.seed.unwrap_or_else(rand::random);
+    let report = run_batch(&options.scenario, replications, threads, base_seed)
+        .map_err(|e| e.to_string())?;
+
+    match options.format {
+        OutputFormat::Text => print!("{}", format_batch(&report)),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        ),
+    }
+    Ok(())
+}
+
+/// Produit cartésien des listes de paramètres, `--replications` simulations par point
+pub fn run_sweep_command(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let (replications, threads) = take_batch_options(&mut args)?;
+
+    let mut spec = SweepSpec::default();
+    if let Some(value) = take_option(&mut args, "--agents")? {
+        spec.agents = parse_list("--agents", &value)?
+            .into_iter()
+            .map(|n| n.round().max(0.0) as usize)
+            .collect();
+    }
+    if let Some(value) = take_option(&mut args, "--k-s")? {
+        spec.k_s = parse_list("--k-s", &value)?.into_iter().map(|x| x as f32).collect();
+    }
+    if let Some(value) = take_option(&mut args, "--friction")? {
+        spec.friction = parse_list("--friction", &value)?.into_iter().map(|x| x as f32).collect();
+    }
+    if let Some(value) = take_option(&mut args, "--pattern")? {
+        spec.patterns = if value == "all" {
+            ObstaclePattern::ALL.to_vec()
+        } else {
+            value.split(',').map(parse_enum).collect::<Result<_, _>>()?
+        };
+    }
+    if let Some(value) = take_option(&mut args, "--movement")? {
+        spec.probabilistic = value
+            .split(',')
+            .map(|mode| match mode {
+                "greedy" => Ok(false),
+                "probabilistic" => Ok(true),
+                other => Err(format!("--movement : mode inconnu '{}'", other)),
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    if let Some(value) = take_option(&mut args, "--neighbourhood")? {
+        spec.neighbourhoods = value.split(',').map(parse_neighbourhood).collect::<Result<_, _>>()?;
+    }
+    if let Some(value) = take_option(&mut args, "--topology")? {
+        spec.topologies = value.split(',').map(parse_enum).collect::<Result<_, _>>()?;
+    }
+    let output = take_option(&mut args, "--output")?;
+
+    let options = RunOptions::parse(&args)?;
+    let base_seed = options.scenario.seed.unwrap_or_else(rand::random);
+    let rows = run_sweep(&options.scenario, &spec, replications, threads, base_seed)
+        .map_err(|e| e.to_string())?;
+
+    let written = match &output {
+        Some(path) => File::create(path).and_then(|file| write_csv(&rows, BufWriter::new(file))),
+        None => write_csv(&rows, io::stdout().lock()),
+    };
+    written.map_err(|e| e.to_string())?;
+
+    if let Some(path) = output {
+        eprintln!("{} simulations écrites dans {}", rows.len(), path);
+    }
+    Ok(())
+}
+
+/// `--replications` et `--threads`, communs à `batch` et `sweep`
+fn take_batch_options(args: &mut Vec<String>) -> Result<(usize, usize), String> {
+    let replications = match take_option(args, "--replications")? {
+        Some(value) => parse_number("--replications", &value)?,
+        None => DEFAULT_REPLICATIONS,
+    };
+    let threads = match take_option(args, "--threads")? {
+        Some(value) => parse_number("--threads", &value)?,
+        None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+    };
+    if replications == 0 {
+        return Err("--replications : au moins une réplication".to_string());
+    }
+    if threads == 0 {
+        return Err("--threads : au moins un fil d'exécution".to_string());
+    }
+    Ok((replications, threads))
+}
+
+fn format_batch(report: &BatchReport) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "Réplications : {} (graines {} à {})\n",
+        report.replications,
+        report.base_seed,
+        report.base_seed.wrapping_add(report.replications.saturating_sub(1) as u64)
+    ));
+    if report.incomplete == report.replications {
+        out.push_str("Nombre d'étapes : aucune réplication terminée\n");
+    } else if report.incomplete > 0 {
+        out.push_str(&format!(
+            "Nombre d'étapes (réplications terminées) : {}\n",
+            format_statistics(&report.steps)
+        ));
+    } else {
+        out.push_str(&format!("Nombre d'étapes : {}\n", format_statistics(&report.steps)));
+    }
+    out.push_str(&format!("Agents évacués : {}\n", format_statistics(&report.evacuated)));
+    for exit in &report.exits {
+        out.push_str(&format!("  {} : {}\n", exit.name, format_statistics(&exit.evacuated)));
+    }
+    if report.incomplete > 0 {
+        out.push_str(&format!("Évacuations incomplètes : {}\n", report.incomplete));
+    }
+    out
+}
+
+fn format_statistics(stats: &Statistics) -> String {
+    format!(
+        "moyenne {:.1} ± {:.1} (IC95 [{:.1}, {:.1}]), min {:.0}, max {:.0}, \
+         p5 {:.1}, p25 {:.1}, médiane {:.1}, p75 {:.1}, p95 {:.1}",
+        stats.mean, stats.std_dev, stats.ci95.0, stats.ci95.1, stats.min, stats.max,
+        stats.p5, stats.p25, stats.median, stats.p75, stats.p95
+    )
+}
+
+fn format_summary(summary: &RunSummary) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("Graine : {}\n", summary.seed));
+    out.push_str(&format!("Nombre d'étapes : {}\n", summary.steps));
+    out.push_str(&format!(
+        "Agents évacués : {} / {}\n",
+        summary.evacuated,
+        summary.initial_agents + summary.injected_agents
+    ));
+    if summary.injected_agents > 0 {
+        out.push_str(&format!("Agents injectés par les sources : {}\n", summary.injected_agents));
+    }
+    out.push_str(&format!("Efficacité : {:.2} agents/étape\n", summary.agents_per_step));
+    for exit in &summary.exits {
+        out.push_str(&format!("  {} : {}\n", exit.name, exit.evacuated));
+    }
+    // Détail par profil dès que la population est hétérogène
+    if summary.profiles.len() > 1 {
+        for profile in &summary.profiles {
+            out.push_str(&format!(
+                "{} : {} / {} évacués, temps moyen {}, attente moyenne {:.1} étapes, distance moyenne {:.1} cellules\n",
+                profile.profile.label(),
+                profile.evacuated,
+                profile.agents,
+                profile
+                    .mean_evacuation_time
+                    .map(|time| format!("{:.1} étapes", time))
+                    .unwrap_or_else(|| "-".to_string()),
+                profile.mean_waiting_steps,
+                profile.mean_path_length
+            ));
+        }
+    }
+    if !summary.completed {
+        out.push_str(&format!("Évacuation incomplète : {} agents restants\n", summary.remaining));
+    }
+    out
+}
+
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Retire `name <valeur>` de la liste d'arguments
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    match args.iter().position(|a| a == name) {
+        Some(i) if i + 1 < args.len() => {
+            let value = args.remove(i + 1);
+            args.remove(i);
+            Ok(Some(value))
+        }
+        Some(_) => Err(format!("{} attend une valeur", name)),
+        None => Ok(None),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} : valeur invalide '{}'", option, value))
+}
+
+/// Liste de nombres : valeurs séparées par des virgules, chacune pouvant
+/// être un intervalle `début:fin:pas` (bornes incluses)
+fn parse_list(option: &str, value: &str) -> Result<Vec<f64>, String> {
+    let mut values = Vec::new();
+    for item in value.split(',') {
+        let bounds: Vec<f64> = item
+            .split(':')
+            .map(|part| parse_number(option, part))
+            .collect::<Result<_, _>>()?;
+        match bounds[..] {
+            [single] => values.push(single),
+            [start, end, step] if step > 0.0 && end >= start => {
+                // Petite marge pour inclure la borne malgré les arrondis
+                let count = ((end - start) / step + 1e-9).floor() as usize;
+                values.extend((0..=count).map(|i| start + i as f64 * step));
+            }
+            _ => return Err(format!("{} : intervalle invalide '{}'", option, item)),
+        }
+    }
+    Ok(values)
+}
+
+/// `von_neumann`, `moore` ou `extended:<rayon>`
+fn parse_neighbourhood(value: &str) -> Result<Neighbourhood, String> {
+    match value.split_once(':') {
+        Some(("extended", radius)) => match parse_number("--neighbourhood", radius)? {
+            0 => Err(format!("--neighbourhood : {}", RADIUS_ERROR)),
+            radius => Ok(Neighbourhood::Extended(radius)),
+        },
+        _ => parse_enum(value),
+    }
+}
+
+/// Lit une valeur d'énumération sous son nom de fichier de scénario (`two_exits_far`...)
+pub fn parse_enum<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    let deserializer: StrDeserializer<ValueError> = value.into_deserializer();
+    T::deserialize(deserializer).map_err(|e| e.to_string())
+}
@@ -0,0 +1,79 @@
+use crate::grid::{CellType, Grid};
+use crate::neighbourhood::{Lattice, Neighbourhood};
+
+/// Champ dynamique (Burstedde/Kirchner) : traces virtuelles laissées par les
+/// agents qui se déplacent, diffusées et atténuées à chaque étape
+pub struct DynamicFloorField {
+    values: Vec<Vec<f32>>,
+    /// Part de la trace diffusée vers les voisins à chaque étape
+    alpha: f32,
+    /// Part de la trace qui disparaît à chaque étape
+    delta: f32,
+}
+
+impl DynamicFloorField {
+    pub fn new(width: usize, height: usize, alpha: f32, delta: f32) -> Self {
+        DynamicFloorField {
+            values: vec![vec![0.0; width]; height],
+            alpha: alpha.clamp(0.0, 1.0),
+            delta: delta.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Un agent quitte la cellule (x, y) : il y laisse une trace
+    pub fn deposit(&mut self, x: usize, y: usize) {
+        if let Some(value) = self.values.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *value += 1.0;
+        }
+    }
+
+    /// Diffusion puis décroissance. La diffusion conserve la masse : chaque
+    /// cellule cède `alpha` de sa trace, répartie entre ses voisins
+    /// cardinaux (hexagones adjacents) qui ne sont pas des murs.
+    pub fn update(&mut self, grid: &Grid) {
+        let height = self.values.len();
+        let width = self.values.first().map(|row| row.len()).unwrap_or(0);
+        let mut next = self.values.clone();
+
+        let lattice = Lattice::new(grid.topology(), Neighbourhood::VonNeumann);
+
+        for y in 0..height {
+            for x in 0..width {
+                let value = self.values[y][x];
+                if value <= 0.0 {
+                    continue;
+                }
+
+                let neighbors: Vec<(usize, usize)> = lattice
+                    .neighbours(x, y)
+                    .into_iter()
+                    .filter(|&(nx, ny)| matches!(grid.get(nx, ny), Some(cell) if cell != CellType::Wall))
+                    .collect();
+
+                if neighbors.is_empty() {
+                    continue;
+                }
+
+                let outflow = self.alpha * value;
+                next[y][x] -= outflow;
+                for (nx, ny) in neighbors.iter().copied() {
+                    next[ny][nx] += outflow / neighbors.len() as f32;
+                }
+            }
+        }
+
+        for value in next.iter_mut().flatten() {
+            *value *= 1.0 - self.delta;
+            // Traces négligeables remises à zéro
+            if *value < 1e-4 {
+                *value = 0.0;
+            }
+        }
+
+        self.values = next;
+    }
+
+    pub fn values(&self) -> &[Vec<f32>] {
+        &self.values
+    }
+}
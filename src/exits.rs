@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use crate::floor_field::FloorField;
+use crate::grid::Grid;
+use crate::neighbourhood::{Lattice, Neighbourhood};
+
+/// Sortie : groupe de cellules de sortie contiguës
+#[derive(Debug, Clone)]
+pub struct Exit {
+    pub id: usize,
+    pub name: String,
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Exit {
+    pub fn width(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// Stratégie de choix de sortie des agents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitStrategy {
+    /// Sortie la plus proche selon son champ
+    #[default]
+    Nearest,
+    /// Sortie avec le moins d'agents en attente devant elle
+    LeastQueued,
+    /// Temps estimé : distance + file d'attente / débit de la sortie
+    EstimatedTime,
+}
+
+/// Sorties de la grille, avec pour chaque cellule l'identifiant de sa sortie
+pub struct ExitSet {
+    exits: Vec<Exit>,
+    cell_exit: Vec<Vec<Option<usize>>>,
+}
+
+impl ExitSet {
+    /// Regroupe les cellules de sortie contiguës (8 voisines, 6 en hexagonal)
+    /// en sorties nommées « Sortie 1 », « Sortie 2 »... dans l'ordre de lecture
+    pub fn from_grid(grid: &Grid) -> Self {
+        let mut cell_exit = vec![vec![None; grid.width()]; grid.height()];
+        let mut exits = Vec::new();
+        let lattice = Lattice::new(grid.topology(), Neighbourhood::Moore);
+
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                if !grid.is_exit(x, y) || cell_exit[y][x].is_some() {
+                    continue;
+                }
+
+                let id = exits.len();
+                let mut cells = Vec::new();
+                let mut stack = vec![(x, y)];
+                cell_exit[y][x] = Some(id);
+
+                while let Some((cx, cy)) = stack.pop() {
+                    cells.push((cx, cy));
+                    for (nx, ny) in lattice.neighbours(cx, cy) {
+                        if grid.is_exit(nx, ny) && cell_exit[ny][nx].is_none() {
+                            cell_exit[ny][nx] = Some(id);
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+
+                cells.sort_by_key(|&(cx, cy)| (cy, cx));
+                exits.push(Exit {
+                    id,
+                    name: format!("Sortie {}", id + 1),
+                    cells,
+                });
+            }
+        }
+
+        ExitSet { exits, cell_exit }
+    }
+
+    pub fn exits(&self) -> &[Exit] {
+        &self.exits
+    }
+
+    /// Sortie à laquelle appartient la cellule (x, y)
+    pub fn exit_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.cell_exit.get(y).and_then(|row| row.get(x)).copied().flatten()
+    }
+
+    /// Choisit la sortie cible d'un agent en (x, y). `queues` donne le nombre
+    /// d'agents en attente devant chaque sortie et `flow` le débit d'une
+    /// cellule de sortie (agents par étape). Retourne `None` si aucune
+    /// sortie n'est atteignable.
+    pub fn choose(
+        &self,
+        strategy: ExitStrategy,
+        fields: &[FloorField],
+        queues: &[usize],
+        flow: f32,
+        (x, y): (usize, usize),
+    ) -> Option<usize> {
+        let cost = |exit: &Exit| -> f32 {
+            let distance = fields[exit.id].static_distances()[y][x];
+            match strategy {
+                ExitStrategy::Nearest => distance,
+                ExitStrategy::LeastQueued => queues[exit.id] as f32,
+                ExitStrategy::EstimatedTime => {
+                    distance + queues[exit.id] as f32 / (flow * exit.width() as f32).max(f32::EPSILON)
+                }
+            }
+        };
+
+        self.exits
+            .iter()
+            .filter(|exit| fields[exit.id].static_distances()[y][x].is_finite())
+            .min_by(|a, b| {
+                // À coût égal, la sortie la plus proche l'emporte
+                cost(a)
+                    .total_cmp(&cost(b))
+                    .then_with(|| {
+                        let da = fields[a.id].static_distances()[y][x];
+                        let db = fields[b.id].static_distances()[y][x];
+                        da.total_cmp(&db)
+                    })
+            })
+            .map(|exit| exit.id)
+    }
+}
@@ -1,122 +1,304 @@
-use std::collections::VecDeque;
-use crate::grid::{Grid, CellType};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use serde::{Deserialize, Serialize};
+use crate::grid::{Grid, CellType, Topology};
+use crate::neighbourhood::{Lattice, Neighbourhood};
+
+/// Méthode de calcul du champ statique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldMethod {
+    /// Plus court chemin sur le voisinage du modèle (distances octiles en Moore)
+    #[default]
+    Dijkstra,
+    /// Fast Marching : solution de l'équation eikonale |grad T| = 1,
+    /// fronts d'onde quasi circulaires et distances proches de l'euclidien
+    FastMarching,
+}
 
 pub struct FloorField {
+    /// Champ utilisé par les agents : distance + terme de murs
     distances: Vec<Vec<f32>>,
+    /// Distance exacte à la sortie la plus proche, sans le terme de murs
+    static_distances: Vec<Vec<f32>>,
+}
+
+/// Noeud de la file de priorité (ordre inversé pour obtenir un tas-min)
+#[derive(Clone, Copy, PartialEq)]
+struct Node {
+    dist: f32,
+    x: usize,
+    y: usize,
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.dist.total_cmp(&self.dist)
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl FloorField {
-    pub fn new(grid: &Grid) -> Self {
-        let width = grid.width();
-        let height = grid.height();
-        let mut distances = vec![vec![f32::INFINITY; width]; height];
-        
-        // Find all exit cells
-        let mut exits = Vec::new();
-        for y in 0..height {
-            for x in 0..width {
-                if grid.is_exit(x, y) {
-                    exits.push((x, y));
+    /// Champ vers les cellules `exits` (toutes les sorties de la grille ou
+    /// une seule). `wall_avoidance` pondère le champ de murs ajouté aux distances :
+    /// positif, les agents s'écartent des murs ; négatif, ils les longent.
+    /// `neighbourhood` donne les pas de Dijkstra (le Fast Marching n'en dépend pas)
+    pub fn new(
+        grid: &Grid,
+        exits: &[(usize, usize)],
+        method: FieldMethod,
+        neighbourhood: Neighbourhood,
+        wall_avoidance: f32,
+    ) -> Self {
+        Self::with_congestion(grid, exits, method, neighbourhood, wall_avoidance, 0.0)
+    }
+
+    /// Champ tenant compte de la foule : traverser une cellule occupée par
+    /// un agent coûte `congestion_penalty` en plus (la cellule reste franchissable).
+    /// Avec un surcoût non nul, `static_distances` n'est plus une distance pure :
+    /// ce champ ne sert qu'à choisir les déplacements
+    pub fn with_congestion(
+        grid: &Grid,
+        exits: &[(usize, usize)],
+        method: FieldMethod,
+        neighbourhood: Neighbourhood,
+        wall_avoidance: f32,
+        congestion_penalty: f32,
+    ) -> Self {
+        let mut static_distances = vec![vec![f32::INFINITY; grid.width()]; grid.height()];
+
+        // Le Fast Marching suppose des cellules carrées : en hexagonal, Dijkstra
+        match (method, grid.topology()) {
+            (FieldMethod::Dijkstra, _) | (FieldMethod::FastMarching, Topology::Hexagonal) => {
+                Self::compute_distances_with_agents(&mut static_distances, exits, grid, neighbourhood, congestion_penalty)
+            }
+            (FieldMethod::FastMarching, Topology::Square) => {
+                Self::fast_marching(&mut static_distances, exits, grid, congestion_penalty)
+            }
+        }
+
+        let wall_field = Self::wall_field(grid);
+        let mut distances = static_distances.clone();
+        for (row, wall_row) in distances.iter_mut().zip(wall_field.iter()) {
+            for (dist, wall) in row.iter_mut().zip(wall_row.iter()) {
+                if dist.is_finite() {
+                    *dist += wall_avoidance * wall;
                 }
             }
         }
-        
-        Self::compute_distances(&mut distances, &exits, grid);
-        
-        FloorField { distances }
-    }
-    
-    fn compute_distances(distances: &mut [Vec<f32>], exits: &[(usize, usize)], grid: &Grid) {
-        Self::compute_distances_with_agents(distances, exits, grid, &[]);
+
+        FloorField { distances, static_distances }
     }
-    
+
+    /// Dijkstra depuis les sorties : chaque pas du voisinage coûte sa longueur
+    /// (1 en cardinal, sqrt(2) en diagonale, 1 entre hexagones adjacents), plus `congestion_penalty` par
+    /// cellule occupée atteinte ; un pas long ne franchit pas de mur
     fn compute_distances_with_agents(
-        distances: &mut [Vec<f32>], 
-        exits: &[(usize, usize)], 
+        distances: &mut [Vec<f32>],
+        exits: &[(usize, usize)],
         grid: &Grid,
-        occupied: &[(usize, usize)]
+        neighbourhood: Neighbourhood,
+        congestion_penalty: f32,
     ) {
-        let mut queue = VecDeque::new();
-        
+        let mut heap = BinaryHeap::new();
+
         for &(x, y) in exits {
             distances[y][x] = 0.0;
-            queue.push_back((x, y, 0.0));
+            heap.push(Node { dist: 0.0, x, y });
         }
-        
-
-        let directions = [
-            // Directions cardinales - coût 1.0
-            (0, -1, 1.0),    // Haut
-            (1,  0, 1.0),    // Gauche
-            (0,  1, 1.0),    // Bas
-            (-1, 0, 1.0),    // Droite
-            // Directions diagonales - coût sqrt(2)
-            (1, -1, 1.414),  // Haut-Droite
-            (1,  1, 1.414),  // Bas-Gauche
-            (-1, 1, 1.414),  // Bas-Droite
-            (-1,-1, 1.414),  // Haut-Droite
-        ];
-        
-        while let Some((x, y, dist)) = queue.pop_front() {
-            for &(dx, dy, cost) in directions.iter() {
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                
-                if nx >= 0 && ny >= 0 {
-                    let nx = nx as usize;
-                    let ny = ny as usize;
-                    
-                    if nx < grid.width() && ny < grid.height() {
+
+        let lattice = Lattice::new(grid.topology(), neighbourhood);
+
+        while let Some(Node { dist, x, y }) = heap.pop() {
+            // Entrée périmée : la cellule a déjà été fixée avec une distance plus courte
+            if dist > distances[y][x] {
+                continue;
+            }
+
+            for (nx, ny) in lattice.neighbours(x, y) {
+                let cost = lattice.step_length((x, y), (nx, ny));
+                let blocked = lattice
+                    .crossed_cells((x, y), (nx, ny))
+                    .into_iter()
+                    .any(|(cx, cy)| grid.get(cx, cy) == Some(CellType::Wall));
+                if blocked {
+                    continue;
+                }
+
+                // Vérifier si la cellule est marchable
+                match grid.get(nx, ny) {
+                    Some(cell_type) if cell_type != CellType::Wall => {
                         let mut new_dist = dist + cost;
-                        
-                        // Vérifier si la cellule est marchable
-                        if let Some(cell_type) = grid.get(nx, ny) {
-                            let is_occupied = occupied.iter().any(|&(ox, oy)| ox == nx && oy == ny);
-                            
-                            if cell_type != CellType::Wall && !is_occupied {
-                                let near_wall = Self::is_near_wall(nx, ny, grid);
-                                if near_wall && new_dist < 10.0 {
-                                    new_dist -= 0.3; 
-                                }
-                                
-                                if distances[ny][nx] > new_dist {
-                                    distances[ny][nx] = new_dist;
-                                    queue.push_back((nx, ny, new_dist));
-                                }
-                            }
+                        if cell_type == CellType::Agent {
+                            new_dist += congestion_penalty;
+                        }
+                        if new_dist < distances[ny][nx] {
+                            distances[ny][nx] = new_dist;
+                            heap.push(Node { dist: new_dist, x: nx, y: ny });
                         }
                     }
+                    _ => {}
                 }
             }
         }
     }
-    
-    fn is_near_wall(x: usize, y: usize, grid: &Grid) -> bool {
-        let neighbors = [
-            (0, -1), (1, 0), (0, 1), (-1, 0),
-            (1, -1), (1, 1), (-1, 1), (-1, -1),
-        ];
-        
-        for (dx, dy) in neighbors.iter() {
-            let nx = x as i32 + dx;
-            let ny = y as i32 + dy;
-            
-            if nx >= 0 && ny >= 0 {
+
+    /// Fast Marching du premier ordre : les cellules sont figées par distance
+    /// croissante, chacune résolvant l'équation eikonale discrète à partir
+    /// de ses voisins cardinaux déjà figés. Les cellules occupées sont
+    /// traversées avec une lenteur de `1 + congestion_penalty`.
+    fn fast_marching(
+        distances: &mut [Vec<f32>],
+        exits: &[(usize, usize)],
+        grid: &Grid,
+        congestion_penalty: f32,
+    ) {
+        let width = grid.width();
+        let height = grid.height();
+        let mut frozen = vec![vec![false; width]; height];
+        let mut heap = BinaryHeap::new();
+
+        for &(x, y) in exits {
+            distances[y][x] = 0.0;
+            heap.push(Node { dist: 0.0, x, y });
+        }
+
+        let cardinals = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+        while let Some(Node { dist, x, y }) = heap.pop() {
+            if frozen[y][x] || dist > distances[y][x] {
+                continue;
+            }
+            frozen[y][x] = true;
+
+            for &(dx, dy) in cardinals.iter() {
+                let nx = x as i32 + dx;
+                let ny = y as i32 + dy;
+                if nx < 0 || ny < 0 {
+                    continue;
+                }
                 let nx = nx as usize;
                 let ny = ny as usize;
-                
-                if let Some(cell_type) = grid.get(nx, ny) {
-                    if cell_type == CellType::Wall {
-                        return true;
-                    }
+
+                let slowness = match grid.get(nx, ny) {
+                    Some(CellType::Agent) => 1.0 + congestion_penalty,
+                    Some(cell) if cell != CellType::Wall => 1.0,
+                    _ => continue,
+                };
+                if frozen[ny][nx] {
+                    continue;
+                }
+
+                let new_dist = Self::eikonal_update(distances, &frozen, nx, ny, slowness);
+                if new_dist < distances[ny][nx] {
+                    distances[ny][nx] = new_dist;
+                    heap.push(Node { dist: new_dist, x: nx, y: ny });
                 }
             }
         }
-        
-        false
     }
-    
+
+    /// Résout (T - a)^2 + (T - b)^2 = f^2 avec a, b les plus petites valeurs
+    /// figées sur chaque axe et f la lenteur de la cellule (schéma upwind, pas de grille = 1)
+    fn eikonal_update(
+        distances: &[Vec<f32>],
+        frozen: &[Vec<bool>],
+        x: usize,
+        y: usize,
+        slowness: f32,
+    ) -> f32 {
+        let value = |cx: Option<usize>, cy: Option<usize>| -> f32 {
+            match (cx, cy) {
+                (Some(cx), Some(cy)) if cy < frozen.len() && cx < frozen[cy].len() && frozen[cy][cx] => {
+                    distances[cy][cx]
+                }
+                _ => f32::INFINITY,
+            }
+        };
+
+        let a = value(x.checked_sub(1), Some(y)).min(value(Some(x + 1), Some(y)));
+        let b = value(Some(x), y.checked_sub(1)).min(value(Some(x), Some(y + 1)));
+
+        if (a - b).abs() >= slowness || a.is_infinite() || b.is_infinite() {
+            a.min(b) + slowness
+        } else {
+            (a + b + (2.0 * slowness * slowness - (a - b) * (a - b)).sqrt()) / 2.0
+        }
+    }
+
+    /// Champ additif de murs : 1 pour les cellules voisines d'un mur, 0 ailleurs
+    /// (les sorties restent à 0 pour ne pas déplacer le puits du champ)
+    pub fn wall_field(grid: &Grid) -> Vec<Vec<f32>> {
+        let mut field = vec![vec![0.0; grid.width()]; grid.height()];
+        for (y, row) in field.iter_mut().enumerate() {
+            for (x, value) in row.iter_mut().enumerate() {
+                if !grid.is_exit(x, y) && Self::is_near_wall(x, y, grid) {
+                    *value = 1.0;
+                }
+            }
+        }
+        field
+    }
+
+    /// Voisinage de Moore (ou les 6 hexagones adjacents)
+    fn is_near_wall(x: usize, y: usize, grid: &Grid) -> bool {
+        Lattice::new(grid.topology(), Neighbourhood::Moore)
+            .neighbours(x, y)
+            .into_iter()
+            .any(|(nx, ny)| grid.get(nx, ny) == Some(CellType::Wall))
+    }
+
     pub fn distances(&self) -> &[Vec<f32>] {
         &self.distances
     }
+
+    /// Distances pures, sans le terme de murs
+    pub fn static_distances(&self) -> &[Vec<f32>] {
+        &self.static_distances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_room(width: usize, height: usize) -> Grid {
+        Grid::from_cells(vec![vec![CellType::Empty; width]; height])
+    }
+
+    fn distance(neighbourhood: Neighbourhood, (x, y): (usize, usize)) -> f32 {
+        let field = FloorField::new(&empty_room(6, 5), &[(0, 0)], FieldMethod::Dijkstra, neighbourhood, 0.0);
+        field.static_distances()[y][x]
+    }
+
+    #[test]
+    fn moore_gives_octile_distances() {
+        assert_eq!(distance(Neighbourhood::Moore, (0, 0)), 0.0);
+        assert_eq!(distance(Neighbourhood::Moore, (4, 0)), 4.0);
+        assert!((distance(Neighbourhood::Moore, (3, 3)) - 3.0 * std::f32::consts::SQRT_2).abs() < 1e-5);
+        assert!((distance(Neighbourhood::Moore, (5, 2)) - (3.0 + 2.0 * std::f32::consts::SQRT_2)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn von_neumann_gives_manhattan_distances() {
+        assert_eq!(distance(Neighbourhood::VonNeumann, (4, 0)), 4.0);
+        assert_eq!(distance(Neighbourhood::VonNeumann, (3, 3)), 6.0);
+        assert_eq!(distance(Neighbourhood::VonNeumann, (5, 2)), 7.0);
+    }
+
+    #[test]
+    fn walls_are_unreachable() {
+        let mut grid = empty_room(3, 1);
+        grid.set(1, 0, CellType::Wall);
+        let field = FloorField::new(&grid, &[(0, 0)], FieldMethod::Dijkstra, Neighbourhood::Moore, 0.0);
+        assert!(field.static_distances()[0][1].is_infinite());
+        assert!(field.static_distances()[0][2].is_infinite());
+    }
 }
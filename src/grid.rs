@@ -1,6 +1,8 @@
-use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::agent::Footprint;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CellType {
     Empty,
     Wall,
@@ -8,7 +10,8 @@ pub enum CellType {
     Exit,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ObstaclePattern {
     Empty,           // Salle vide
     Single,          // Un pilier central
@@ -20,10 +23,52 @@ pub enum ObstaclePattern {
     TwoExitsFar,     // Deux sorties éloignées sur le mur droit // Non utilisé
 }
 
+impl ObstaclePattern {
+    /// Toutes les salles prédéfinies, dans l'ordre du menu
+    pub const ALL: [ObstaclePattern; 8] = [
+        ObstaclePattern::Empty,
+        ObstaclePattern::Single,
+        ObstaclePattern::Rooms,
+        ObstaclePattern::ExitObstacle,
+        ObstaclePattern::MultiObstacles,
+        ObstaclePattern::Labyrinth,
+        ObstaclePattern::TwoExitsAdjacent,
+        ObstaclePattern::TwoExitsFar,
+    ];
+
+    /// Taille minimale (largeur, hauteur) pour laquelle la salle se construit :
+    /// murs d'enceinte et sortie, plus la marge de 8 cellules du labyrinthe et
+    /// de l'obstacle devant la sortie, et l'écart entre les deux sorties
+    pub fn min_size(self) -> (usize, usize) {
+        match self {
+            ObstaclePattern::Labyrinth => (8, 8),
+            ObstaclePattern::ExitObstacle => (8, 3),
+            ObstaclePattern::TwoExitsAdjacent => (3, 6),
+            ObstaclePattern::TwoExitsFar => (3, 4),
+            _ => (3, 3),
+        }
+    }
+}
+
+/// Pavage de la grille
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    /// Cellules carrées
+    #[default]
+    Square,
+    /// Hexagones en coordonnées décalées « odd-r » : la cellule (x, y) garde
+    /// ses indices, les lignes impaires sont décalées d'une demi-cellule vers
+    /// la droite et chaque cellule a 6 voisines
+    Hexagonal,
+}
+
+#[derive(Clone)]
 pub struct Grid {
     width: usize,
     height: usize,
     cells: Vec<Vec<CellType>>,
+    topology: Topology,
 }
 
 impl Grid {    
@@ -33,6 +78,7 @@ impl Grid {
             width,
             height,
             cells,
+            topology: Topology::Square,
         };
         
         // Initialize with walls on borders
@@ -53,7 +99,56 @@ impl Grid {
         
         grid
     }
-    
+
+    /// Construit une grille à partir de lignes de cellules de même longueur
+    pub fn from_cells(cells: Vec<Vec<CellType>>) -> Self {
+        let height = cells.len();
+        let width = cells.first().map(|row| row.len()).unwrap_or(0);
+        Grid {
+            width,
+            height,
+            cells,
+            topology: Topology::Square,
+        }
+    }
+
+    /// Même grille sur un autre pavage : les cellules gardent leurs indices,
+    /// un plan carré se relit donc tel quel en hexagonal
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Grille des positions possibles d'un agent encombrant (cellule en haut
+    /// à gauche) : mur si l'encombrement posé là touche un mur ou dépasse de
+    /// la grille, sortie s'il recouvre une sortie, agent s'il recouvre un
+    /// agent (pour le surcoût de congestion), vide sinon
+    pub fn for_footprint(&self, footprint: Footprint) -> Grid {
+        let cells = (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        let mut cell = CellType::Empty;
+                        for (cx, cy) in footprint.cells(x, y) {
+                            match self.get(cx, cy) {
+                                None | Some(CellType::Wall) => return CellType::Wall,
+                                Some(CellType::Exit) => cell = CellType::Exit,
+                                Some(CellType::Agent) if cell == CellType::Empty => cell = CellType::Agent,
+                                _ => {}
+                            }
+                        }
+                        cell
+                    })
+                    .collect()
+            })
+            .collect();
+        Grid::from_cells(cells).with_topology(self.topology)
+    }
+
     fn initialize_walls(&mut self) {
         // Top and bottom walls
         for x in 0..self.width {
@@ -131,7 +226,7 @@ impl Grid {
                 ];
                 
                 for (px, py, size) in obstacles.iter() {
-                    let size = *size as i32;
+                    let size = *size;
                     for dy in -size..=size {
                         for dx in -size..=size {
                             let y = *py as i32 + dy;
@@ -297,10 +392,23 @@ impl Grid {
     pub fn is_exit(&self, x: usize, y: usize) -> bool {
         matches!(self.get(x, y), Some(CellType::Exit))
     }
+
+    /// Vrai si la grille contient au moins une cellule de sortie
+    pub fn has_exit(&self) -> bool {
+        self.cells.iter().flatten().any(|&cell| cell == CellType::Exit)
+    }
     
     pub fn is_walkable(&self, x: usize, y: usize) -> bool {
         matches!(self.get(x, y), Some(CellType::Empty | CellType::Exit))
     }
+
+    /// Vrai si l'encombrement posé en (x, y) ne recouvre que des cellules
+    /// praticables ou les cellules de `own` (l'agent lui-même)
+    pub fn fits(&self, footprint: Footprint, x: usize, y: usize, own: &[(usize, usize)]) -> bool {
+        footprint
+            .cells(x, y)
+            .all(|(cx, cy)| self.is_walkable(cx, cy) || own.contains(&(cx, cy)))
+    }
     
     pub fn width(&self) -> usize {
         self.width
@@ -309,23 +417,4 @@ impl Grid {
     pub fn height(&self) -> usize {
         self.height
     }
-    
-    pub fn draw(&self, cell_size: f32) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let px = x as f32 * cell_size;
-                let py = y as f32 * cell_size;
-                
-                let color = match self.cells[y][x] {
-                    CellType::Empty => Color::new(0.95, 0.95, 0.95, 1.0),
-                    CellType::Wall => Color::new(0.2, 0.2, 0.2, 1.0),
-                    CellType::Agent => Color::new(0.2, 0.5, 0.9, 1.0),
-                    CellType::Exit => Color::new(0.2, 0.8, 0.2, 1.0),
-                };
-                
-                draw_rectangle(px, py, cell_size, cell_size, color);
-                draw_rectangle_lines(px, py, cell_size, cell_size, 0.5, Color::new(0.8, 0.8, 0.8, 1.0));
-            }
-        }
-    }
 }
@@ -0,0 +1,244 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::agent::Footprint;
+use crate::grid::{CellType, Topology};
+use crate::neighbourhood::{Lattice, Neighbourhood};
+use crate::recorder::StepObserver;
+use crate::simulation::Simulation;
+
+/// Carte affichable ou exportable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapLayer {
+    /// Part du temps où la cellule est occupée
+    Occupancy,
+    /// Nombre d'entrées d'agents dans la cellule (apparition comprise)
+    Passages,
+    /// Densité locale maximale : part des cellules occupées sur la fenêtre 3x3 (7 hexagones en pavage hexagonal)
+    MaxDensity,
+    /// Attente moyenne (en étapes) d'un agent passé par la cellule
+    MeanWaiting,
+}
+
+impl HeatmapLayer {
+    pub const ALL: [HeatmapLayer; 4] = [
+        HeatmapLayer::Occupancy,
+        HeatmapLayer::Passages,
+        HeatmapLayer::MaxDensity,
+        HeatmapLayer::MeanWaiting,
+    ];
+
+    /// Nom utilisé dans les fichiers exportés
+    pub fn key(self) -> &'static str {
+        match self {
+            HeatmapLayer::Occupancy => "occupancy",
+            HeatmapLayer::Passages => "passages",
+            HeatmapLayer::MaxDensity => "max_density",
+            HeatmapLayer::MeanWaiting => "mean_waiting",
+        }
+    }
+
+    /// Libellé affiché dans l'interface
+    pub fn label(self) -> &'static str {
+        match self {
+            HeatmapLayer::Occupancy => "Occupation",
+            HeatmapLayer::Passages => "Passages",
+            HeatmapLayer::MaxDensity => "Densité max",
+            HeatmapLayer::MeanWaiting => "Attente moyenne",
+        }
+    }
+}
+
+/// Statistiques par cellule accumulées au fil d'une exécution, pour repérer
+/// les zones de congestion
+#[derive(Debug, Clone)]
+pub struct Heatmaps {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    /// Nombre d'états observés (état initial compris)
+    samples: usize,
+    occupied: Vec<Vec<u32>>,
+    passages: Vec<Vec<u32>>,
+    max_density: Vec<Vec<f32>>,
+    waiting: Vec<Vec<u32>>,
+    observer: StepObserver,
+}
+
+/// Export JSON : une grille (lignes de cellules) par carte
+#[derive(Serialize)]
+struct HeatmapsExport {
+    width: usize,
+    height: usize,
+    samples: usize,
+    occupancy: Vec<Vec<f32>>,
+    passages: Vec<Vec<f32>>,
+    max_density: Vec<Vec<f32>>,
+    mean_waiting: Vec<Vec<f32>>,
+}
+
+impl Heatmaps {
+    /// Démarre l'accumulation avec l'état courant de la simulation
+    pub fn new(simulation: &Simulation) -> Self {
+        let (width, height) = (simulation.grid().width(), simulation.grid().height());
+        let mut heatmaps = Heatmaps {
+            width,
+            height,
+            topology: simulation.grid().topology(),
+            samples: 0,
+            occupied: vec![vec![0; width]; height],
+            passages: vec![vec![0; width]; height],
+            max_density: vec![vec![0.0; width]; height],
+            waiting: vec![vec![0; width]; height],
+            observer: StepObserver::default(),
+        };
+        heatmaps.record(simulation);
+        heatmaps
+    }
+
+    /// Ajoute l'état après la dernière étape jouée
+    pub fn record(&mut self, simulation: &Simulation) {
+        let Some(observation) = self.observer.observe(simulation) else {
+            return;
+        };
+        self.samples += 1;
+
+        // Agents sortis : dernier passage sur la sortie. Agents présents : toutes
+        // les cellules d'un agent encombrant, attente s'il n'a pas bougé, passage
+        // sur les cellules nouvellement couvertes sinon
+        for agent in &observation.agents {
+            let (x, y) = agent.position;
+            let waited = !agent.evacuated && agent.previous == Some(agent.position);
+            for (cx, cy) in agent.footprint.cells(x, y) {
+                if !agent.evacuated {
+                    self.occupied[cy][cx] += 1;
+                }
+                if waited {
+                    self.waiting[cy][cx] += 1;
+                } else if !covered(agent.previous, agent.footprint, (cx, cy)) {
+                    self.passages[cy][cx] += 1;
+                }
+            }
+        }
+
+        // Fenêtre de densité : la cellule et ses voisines de Moore
+        let grid = simulation.grid();
+        let window = Lattice::new(grid.topology(), Neighbourhood::Moore);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if grid.get(x, y) == Some(CellType::Wall) {
+                    continue;
+                }
+                let mut cells = 0;
+                let mut occupied = 0;
+                for (wx, wy) in std::iter::once((x, y)).chain(window.neighbours(x, y)) {
+                    match grid.get(wx, wy) {
+                        Some(CellType::Wall) | None => {}
+                        Some(cell) => {
+                            cells += 1;
+                            if cell == CellType::Agent {
+                                occupied += 1;
+                            }
+                        }
+                    }
+                }
+                let density = occupied as f32 / cells as f32;
+                if density > self.max_density[y][x] {
+                    self.max_density[y][x] = density;
+                }
+            }
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Pavage de la grille observée
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Valeurs d'une carte, sous forme de grille `[y][x]`
+    pub fn layer(&self, layer: HeatmapLayer) -> Vec<Vec<f32>> {
+        match layer {
+            HeatmapLayer::Occupancy => map_grid(&self.occupied, |count| count as f32 / self.samples.max(1) as f32),
+            HeatmapLayer::Passages => map_grid(&self.passages, |count| count as f32),
+            HeatmapLayer::MaxDensity => self.max_density.clone(),
+            HeatmapLayer::MeanWaiting => self
+                .waiting
+                .iter()
+                .zip(&self.passages)
+                .map(|(waiting, passages)| {
+                    waiting
+                        .iter()
+                        .zip(passages)
+                        .map(|(&w, &p)| if p > 0 { w as f32 / p as f32 } else { 0.0 })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Une carte en CSV : une ligne de texte par ligne de cellules
+    pub fn write_csv(&self, layer: HeatmapLayer, mut out: impl Write) -> io::Result<()> {
+        for row in self.layer(layer) {
+            let values: Vec<String> = row.iter().map(|value| format!("{:.4}", value)).collect();
+            writeln!(out, "{}", values.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Toutes les cartes dans un même document JSON
+    pub fn write_json(&self, out: impl Write) -> io::Result<()> {
+        let export = HeatmapsExport {
+            width: self.width,
+            height: self.height,
+            samples: self.samples,
+            occupancy: self.layer(HeatmapLayer::Occupancy),
+            passages: self.layer(HeatmapLayer::Passages),
+            max_density: self.layer(HeatmapLayer::MaxDensity),
+            mean_waiting: self.layer(HeatmapLayer::MeanWaiting),
+        };
+        serde_json::to_writer_pretty(out, &export).map_err(io::Error::from)
+    }
+
+    /// `cartes.json` écrit toutes les cartes dans un fichier ; tout autre
+    /// chemin sert de préfixe à un CSV par carte (`cartes_occupancy.csv`...)
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            let mut out = BufWriter::new(File::create(path)?);
+            self.write_json(&mut out)?;
+            out.flush()?;
+            return Ok(vec![path.to_path_buf()]);
+        }
+
+        let stem = path.with_extension("");
+        let mut written = Vec::new();
+        for layer in HeatmapLayer::ALL {
+            let mut name = stem.clone().into_os_string();
+            name.push(format!("_{}.csv", layer.key()));
+            let layer_path = PathBuf::from(name);
+            let mut out = BufWriter::new(File::create(&layer_path)?);
+            self.write_csv(layer, &mut out)?;
+            out.flush()?;
+            written.push(layer_path);
+        }
+        Ok(written)
+    }
+}
+
+/// Vrai si `cell` était déjà couverte par l'agent à sa position précédente
+fn covered(previous: Option<(usize, usize)>, footprint: Footprint, cell: (usize, usize)) -> bool {
+    previous.is_some_and(|(x, y)| footprint.cells(x, y).any(|c| c == cell))
+}
+
+fn map_grid(values: &[Vec<u32>], f: impl Fn(u32) -> f32) -> Vec<Vec<f32>> {
+    values.iter().map(|row| row.iter().map(|&v| f(v)).collect()).collect()
+}
@@ -0,0 +1,34 @@
+//! Modèle d'évacuation par automate cellulaire et champ de plancher.
+//!
+//! Le cœur du modèle (`Grid`, `FloorField`, `Agent`, `Simulation`...) ne dépend
+//! d'aucune bibliothèque graphique ; le rendu macroquad est dans `render`,
+//! activé par la feature `gui` (par défaut).
+
+pub mod agent;
+pub mod batch;
+pub mod cli;
+pub mod dynamic_field;
+pub mod exits;
+pub mod floor_field;
+pub mod grid;
+pub mod heatmap;
+pub mod map;
+pub mod measurement;
+pub mod neighbourhood;
+pub mod profile;
+pub mod raster;
+pub mod recorder;
+pub mod scenario;
+pub mod simulation;
+pub mod source;
+pub mod spawn;
+pub mod sweep;
+
+#[cfg(feature = "gui")]
+pub mod render;
+
+pub use agent::Agent;
+pub use floor_field::FloorField;
+pub use grid::{CellType, Grid, ObstaclePattern};
+pub use scenario::Scenario;
+pub use simulation::Simulation;
@@ -1,11 +1,11 @@
-mod grid;
-mod agent;
-mod floor_field;
-mod simulation;
-
 use macroquad::prelude::*;
-use simulation::Simulation;
-use grid::ObstaclePattern;
+use automates_evacuation::cli;
+use automates_evacuation::grid::{Grid, ObstaclePattern, Topology};
+use automates_evacuation::heatmap::{HeatmapLayer, Heatmaps};
+use automates_evacuation::map::MapLayout;
+use automates_evacuation::raster::RasterOptions;
+use automates_evacuation::scenario::Scenario;
+use automates_evacuation::simulation::Simulation;
 
 const GRID_WIDTH: usize = 60;
 const GRID_HEIGHT: usize = 40;
@@ -67,12 +67,18 @@ fn window_conf() -> Conf {
     Conf {
         window_title: "Projet Automate : Évacuation".to_owned(),
         window_width: 920, 
-        window_height: 750, 
+        window_height: 800, 
 
         ..Default::default()
     }
 }
 
+/// Salle passée en argument : plan (texte ou image) ou scénario complet
+enum CustomRoom {
+    Map(MapLayout),
+    Scenario(Box<Scenario>),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum AppState {
     Menu,           // Menu de départ
@@ -80,32 +86,62 @@ enum AppState {
     Complete,       // Écran de fin
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    
+    // Mode sans fenêtre : `automates-evacuation headless [run|batch] [options]`
+    if args.first().map(String::as_str) == Some("headless") {
+        if let Err(err) = cli::run(&args[1..]) {
+            eprintln!("Erreur : {}", err);
+            std::process::exit(2);
+        }
+        return;
+    }
+    
+    macroquad::Window::from_config(window_conf(), run_gui(args.first().cloned()));
+}
+
+async fn run_gui(custom_path: Option<String>) {
     let mut app_state = AppState::Menu;
     let mut selected_room = 0;
     let mut num_agents = 200;
     let mut agent_input = String::new();
+    // Pavage des salles prédéfinies et des plans chargés ([T])
+    let mut topology = Topology::Square;
     
     let mut simulation: Option<Simulation> = None;
     let mut paused = false;
     let mut step_by_step = false;
     let mut last_step_time = get_time();
     let step_interval = 1.0 / STEPS_PER_SECOND;
-    let mut initial_agent_count = 200;
+    // Cartes de chaleur de la simulation en cours et carte affichée ([H])
+    let mut heatmaps: Option<Heatmaps> = None;
+    let mut heatmap_layer: Option<usize> = None;
+    
+    // Plan ou scénario optionnel passé en argument :
+    // `cargo run -- plan.txt` (ou .png/.bmp, .json/.toml)
+    let custom_room = custom_path.and_then(|path| match load_custom_room(&path) {
+        Ok(room) => Some((path, room)),
+        Err(err) => {
+            eprintln!("Impossible de charger {} : {}", path, err);
+            None
+        }
+    });
     
     loop {
         clear_background(WHITE);
         
         match app_state {
             AppState::Menu => {
-                draw_menu(&mut selected_room, &mut num_agents, &mut agent_input, &mut app_state, &mut simulation, &mut initial_agent_count, &mut last_step_time);
+                draw_menu(&mut selected_room, &mut num_agents, &mut agent_input, &mut topology, &mut app_state, &mut simulation, &mut last_step_time, custom_room.as_ref());
             },
             
             AppState::Simulation => {
                 let should_pause = is_key_pressed(KeyCode::Space);
                 let should_step = is_key_pressed(KeyCode::S);
                 let should_exit = is_key_pressed(KeyCode::Escape);
+                let should_save = is_key_pressed(KeyCode::M);
+                let should_cycle_heatmap = is_key_pressed(KeyCode::H);
                 
                 if should_pause {
                     paused = !paused;
@@ -116,40 +152,71 @@ async fn main() {
                 if should_exit {
                     app_state = AppState::Menu;
                     simulation = None;
+                    heatmaps = None;
                     paused = false;
                 }
+                if should_cycle_heatmap {
+                    heatmap_layer = match heatmap_layer {
+                        None => Some(0),
+                        Some(i) if i + 1 < HeatmapLayer::ALL.len() => Some(i + 1),
+                        Some(_) => None,
+                    };
+                }
+                
+                if should_save {
+                    if let Some(ref sim) = simulation {
+                        let path = format!("salle_{}.txt", sim.step_count());
+                        match sim.layout().save(&path) {
+                            Ok(()) => println!("Plan enregistré dans {}", path),
+                            Err(err) => eprintln!("Impossible d'enregistrer {} : {}", path, err),
+                        }
+                    }
+                }
                 
                 if let Some(ref mut sim) = simulation {
+                    let heatmaps = heatmaps.get_or_insert_with(|| Heatmaps::new(sim));
                     if !paused || step_by_step {
                         let current_time = get_time();
                         if step_by_step || current_time - last_step_time >= step_interval {
                             sim.step();
+                            heatmaps.record(sim);
                             last_step_time = current_time;
                             step_by_step = false;
                         }
                     }
                     
-                    let is_complete = sim.agent_count() == 0;
+                    let is_complete = sim.is_finished();
                     if is_complete {
                         app_state = AppState::Complete;
                         paused = true;
                     }
                     
                     sim.draw(CELL_SIZE);
+                    let layer = heatmap_layer.map(|i| HeatmapLayer::ALL[i]);
+                    if let Some(layer) = layer {
+                        heatmaps.draw(layer, CELL_SIZE);
+                    }
                     
+                    let layer_label = layer.map(HeatmapLayer::label).unwrap_or("aucune");
                     draw_text(
-                        &format!("Agents: {} | Steps: {}", sim.agent_count(), sim.step_count()),
+                        &format!(
+                            "Agents: {} | Steps: {} | Graine: {} | Carte: {}",
+                            sim.agent_count(), sim.step_count(), sim.seed(), layer_label
+                        ),
                         10.0, screen_height() - 50.0, 20.0, BLACK
                     );
                     
-                    let room = ROOM_CONFIGS[selected_room];
+                    let room_name = match ROOM_CONFIGS.get(selected_room) {
+                        Some(room) => room.name,
+                        None => "Plan chargé",
+                    };
                     draw_text(
-                        &format!("Salle: {} | Population: {}", room.name, num_agents),
+                        &format!("Salle: {} | Population: {}", room_name, num_agents),
                         10.0, screen_height() - 30.0, 18.0, Color::new(0.2, 0.4, 0.8, 1.0)
                     );
                     
                     draw_text(
-                        "[SPACE] Pause | [S] Step | [ESC] Menu",
+                        "[SPACE] Pause | [S] Step | [H] Carte de chaleur | [M] Enregistrer le plan | [ESC] Menu",
                         10.0, screen_height() - 10.0, 16.0, DARKGRAY
                     );
                     
@@ -164,6 +231,9 @@ async fn main() {
                 if let Some(ref sim) = simulation {
                     // Afficher la grille finale
                     sim.draw(CELL_SIZE);
+                    if let (Some(i), Some(heatmaps)) = (heatmap_layer, &heatmaps) {
+                        heatmaps.draw(HeatmapLayer::ALL[i], CELL_SIZE);
+                    }
                     
                     // Overlay de fin
                     let screen_w = screen_width();
@@ -172,7 +242,7 @@ async fn main() {
                     draw_rectangle(0.0, 0.0, screen_w, screen_h, Color::new(0.0, 0.0, 0.0, 0.6));
                     
                     let box_w = 500.0;
-                    let box_h = 320.0;
+                    let box_h = 350.0;
                     let box_x = (screen_w - box_w) / 2.0;
                     let box_y = (screen_h - box_h) / 2.0;
                     
@@ -185,8 +255,13 @@ async fn main() {
                     let mut y_offset = box_y + 100.0;
                     let line_height = 35.0;
                     
+                    let summary = sim.summary();
                     draw_text(
-                        &format!("Agents évacués : {} / {}", initial_agent_count, initial_agent_count),
+                        &format!(
+                            "Agents évacués : {} / {}",
+                            summary.evacuated,
+                            summary.initial_agents + summary.injected_agents
+                        ),
                         stats_x, y_offset, 25.0, BLACK
                     );
                     y_offset += line_height;
@@ -197,12 +272,20 @@ async fn main() {
                     );
                     y_offset += line_height;
                     
-                    let efficiency = initial_agent_count as f32 / sim.step_count() as f32;
                     draw_text(
-                        &format!("Efficacité : {:.2} agents/étape", efficiency),
+                        &format!("Efficacité : {:.2} agents/étape", summary.agents_per_step),
                         stats_x, y_offset, 25.0, BLACK
                     );
-                    y_offset += line_height + 10.0;
+                    y_offset += line_height;
+                    
+                    let per_exit: Vec<String> = sim
+                        .exits()
+                        .iter()
+                        .zip(sim.evacuated_per_exit())
+                        .map(|(exit, count)| format!("{} : {}", exit.name, count))
+                        .collect();
+                    draw_text(&per_exit.join(" | "), stats_x, y_offset, 20.0, DARKGRAY);
+                    y_offset += 10.0;
                     
                     draw_rectangle(box_x + 20.0, y_offset, box_w - 40.0, 60.0, Color::new(0.9, 0.95, 0.9, 1.0));
                     draw_text(
@@ -214,6 +297,7 @@ async fn main() {
                     if is_key_pressed(KeyCode::Enter) {
                         app_state = AppState::Menu;
                         simulation = None;
+                        heatmaps = None;
                         paused = false;
                     }
                 }
@@ -224,14 +308,29 @@ async fn main() {
     }
 }
 
+fn load_custom_room(path: &str) -> Result<CustomRoom, String> {
+    let lower = path.to_lowercase();
+    if lower.ends_with(".json") || lower.ends_with(".toml") {
+        Scenario::load(path).map(|scenario| CustomRoom::Scenario(Box::new(scenario))).map_err(|e| e.to_string())
+    } else if lower.ends_with(".png") || lower.ends_with(".bmp") {
+        Grid::from_image(path, &RasterOptions::default())
+            .map(|grid| CustomRoom::Map(MapLayout::from(grid)))
+            .map_err(|e| e.to_string())
+    } else {
+        MapLayout::load(path).map(CustomRoom::Map).map_err(|e| e.to_string())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn draw_menu(
     selected_room: &mut usize,
     num_agents: &mut usize,
     agent_input: &mut String,
+    topology: &mut Topology,
     app_state: &mut AppState,
     simulation: &mut Option<Simulation>,
-    initial_agent_count: &mut usize,
     last_step_time: &mut f64,
+    custom_room: Option<&(String, CustomRoom)>,
 ) {
     let screen_w = screen_width();
     let screen_h = screen_height();
@@ -244,7 +343,7 @@ fn draw_menu(
     draw_text("Configuration de la simulation", screen_w / 2.0 - 180.0, 100.0, 22.0, Color::new(0.8, 0.8, 0.8, 1.0));
 
     let box_w = 700.0;
-    let box_h = 580.0;
+    let box_h = if custom_room.is_some() { 670.0 } else { 620.0 };
     let box_x = (screen_w - box_w) / 2.0;
     let box_y = 130.0;
     
@@ -257,7 +356,8 @@ fn draw_menu(
     if is_key_pressed(KeyCode::Up) && *selected_room > 0 {
         *selected_room -= 1;
     }
-    if is_key_pressed(KeyCode::Down) && *selected_room < ROOM_CONFIGS.len() - 1 {
+    let room_count = ROOM_CONFIGS.len() + custom_room.is_some() as usize;
+    if is_key_pressed(KeyCode::Down) && *selected_room < room_count - 1 {
         *selected_room += 1;
     }
     
@@ -289,6 +389,29 @@ fn draw_menu(
         y_offset += 50.0;
     }
     
+    if let Some((path, room)) = custom_room {
+        let is_selected = *selected_room == ROOM_CONFIGS.len();
+        let item_x = box_x + 30.0;
+        let bg_color = if is_selected {
+            Color::new(0.6, 0.75, 1.0, 1.0)
+        } else {
+            Color::new(0.85, 0.85, 0.85, 1.0)
+        };
+        draw_rectangle(item_x, y_offset - 30.0, box_w - 60.0, 40.0, bg_color);
+        let label = match room {
+            CustomRoom::Map(_) => format!("Plan chargé - {}", path),
+            CustomRoom::Scenario(scenario) => format!(
+                "Scénario - {}",
+                scenario.name.as_deref().unwrap_or(path)
+            ),
+        };
+        draw_text(
+            &format!("[{}] {}", ROOM_CONFIGS.len() + 1, label),
+            item_x + 10.0, y_offset, 20.0, BLACK
+        );
+        y_offset += 50.0;
+    }
+    
     // Section nombre d'agents
     draw_line(box_x + 30.0, y_offset, box_x + box_w - 30.0, y_offset, 1.0, GRAY);
     y_offset += 20.0;
@@ -312,6 +435,22 @@ fn draw_menu(
         agent_input.clone()
     };
     draw_text(&display_text, input_x + 10.0, input_y + 28.0, 25.0, BLACK);
+
+    // Pavage de la grille (les scénarios gardent le leur)
+    if is_key_pressed(KeyCode::T) {
+        *topology = match *topology {
+            Topology::Square => Topology::Hexagonal,
+            Topology::Hexagonal => Topology::Square,
+        };
+    }
+    let topology_name = match *topology {
+        Topology::Square => "carré",
+        Topology::Hexagonal => "hexagonal",
+    };
+    draw_text(
+        &format!("Pavage : {}  [T] pour changer", topology_name),
+        input_x, input_y + input_h + 30.0, 20.0, Color::new(0.2, 0.4, 0.7, 1.0)
+    );
     
     // Capture de la saisie
     if let Some(character) = get_char_pressed() {
@@ -342,17 +481,35 @@ fn draw_menu(
         }
         
         // Créer la simulation
-        let room = ROOM_CONFIGS[*selected_room];
-        *simulation = Some(Simulation::new_with_pattern(
-            GRID_WIDTH,
-            GRID_HEIGHT,
-            *num_agents,
-            K_S,
-            room.pattern
-        ));
-        *initial_agent_count = *num_agents;
-        *last_step_time = get_time();
-        *app_state = AppState::Simulation;
+        let created = match (ROOM_CONFIGS.get(*selected_room), custom_room) {
+            (Some(room), _) => {
+                let grid = Grid::new_with_pattern(GRID_WIDTH, GRID_HEIGHT, room.pattern).with_topology(*topology);
+                Simulation::new_with_map(MapLayout::from(grid), *num_agents, K_S)
+                    .map_err(|err| format!("Salle {} : {}", room.name, err))
+            }
+            (None, Some((path, CustomRoom::Map(layout)))) => {
+                let mut layout = layout.clone();
+                layout.grid = layout.grid.with_topology(*topology);
+                Simulation::new_with_map(layout, *num_agents, K_S)
+                    .map_err(|err| format!("Plan {} : {}", path, err))
+            }
+            (None, Some((path, CustomRoom::Scenario(scenario)))) => {
+                Simulation::from_scenario(scenario)
+                    .map_err(|err| format!("Scénario {} invalide : {}", path, err))
+            }
+            (None, None) => unreachable!(),
+        };
+        *simulation = match created {
+            Ok(sim) => Some(sim),
+            Err(err) => {
+                eprintln!("{}", err);
+                None
+            }
+        };
+        if simulation.is_some() {
+            *last_step_time = get_time();
+            *app_state = AppState::Simulation;
+        }
     }
     
     let instructions_y = box_y + box_h + 25.0;
@@ -0,0 +1,251 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::grid::{CellType, Grid};
+use crate::spawn::SpawnGroup;
+
+// Légende des fichiers de plan ASCII
+pub const WALL_CHAR: char = '#';
+pub const EMPTY_CHAR: char = '.';
+pub const EXIT_CHAR: char = 'E';
+pub const AGENT_CHAR: char = 'A';
+pub const SPAWN_CHAR: char = 'S';
+
+/// Plan de salle lu depuis un fichier texte :
+/// la grille, les agents pré-placés (`A`) et la zone d'apparition (`S`)
+#[derive(Clone)]
+pub struct MapLayout {
+    pub grid: Grid,
+    pub agents: Vec<(usize, usize)>,
+    pub spawn_cells: Vec<(usize, usize)>,
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Io(io::Error),
+    Image(image::ImageError),
+    Empty,
+    RaggedLine { line: usize, expected: usize, found: usize },
+    UnknownChar { line: usize, column: usize, found: char },
+    NoExit,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(err) => write!(f, "erreur de lecture : {}", err),
+            MapError::Image(err) => write!(f, "image illisible : {}", err),
+            MapError::Empty => write!(f, "le plan est vide"),
+            MapError::RaggedLine { line, expected, found } => write!(
+                f,
+                "ligne {} : {} colonnes au lieu de {}",
+                line, found, expected
+            ),
+            MapError::UnknownChar { line, column, found } => write!(
+                f,
+                "ligne {}, colonne {} : caractère inconnu '{}'",
+                line, column, found
+            ),
+            MapError::NoExit => write!(f, "le plan ne contient aucune sortie ('{}')", EXIT_CHAR),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl From<io::Error> for MapError {
+    fn from(err: io::Error) -> Self {
+        MapError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for MapError {
+    fn from(err: image::ImageError) -> Self {
+        MapError::Image(err)
+    }
+}
+
+impl MapLayout {
+    /// `count` agents tirés dans la zone d'apparition `S`, ou dans toute la
+    /// salle si le plan n'en a pas
+    pub fn random_group(&self, count: usize) -> SpawnGroup {
+        if self.spawn_cells.is_empty() {
+            SpawnGroup::new(
+                "salle".to_string(),
+                (0..self.grid.height())
+                    .flat_map(|y| (0..self.grid.width()).map(move |x| (x, y)))
+                    .collect(),
+                count,
+            )
+        } else {
+            SpawnGroup::new("zone d'apparition S".to_string(), self.spawn_cells.clone(), count)
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, MapError> {
+        let layout = Self::parse_plan(text)?;
+        if !layout.grid.has_exit() {
+            return Err(MapError::NoExit);
+        }
+        Ok(layout)
+    }
+
+    /// Plan lu sans exiger de sortie : un scénario peut ajouter les siennes
+    pub(crate) fn load_plan(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        let text = fs::read_to_string(path)?;
+        Self::parse_plan(&text)
+    }
+
+    fn parse_plan(text: &str) -> Result<Self, MapError> {
+        // Les lignes vides en début et en fin de fichier sont ignorées ;
+        // `first` garde les numéros de ligne du fichier dans les erreurs
+        let lines: Vec<&str> = text.lines().collect();
+        let first = lines.iter().position(|l| !l.trim_end().is_empty());
+        let last = lines.iter().rposition(|l| !l.trim_end().is_empty());
+        let (first, lines) = match (first, last) {
+            (Some(first), Some(last)) => (first, &lines[first..=last]),
+            _ => return Err(MapError::Empty),
+        };
+
+        let width = lines[0].trim_end().chars().count();
+        let mut cells = Vec::with_capacity(lines.len());
+        let mut agents = Vec::new();
+        let mut spawn_cells = Vec::new();
+
+        for (y, line) in lines.iter().enumerate() {
+            let line = line.trim_end();
+            let found = line.chars().count();
+            if found != width {
+                return Err(MapError::RaggedLine { line: first + y + 1, expected: width, found });
+            }
+
+            let mut row = Vec::with_capacity(width);
+            for (x, c) in line.chars().enumerate() {
+                let cell = match c {
+                    WALL_CHAR => CellType::Wall,
+                    EMPTY_CHAR => CellType::Empty,
+                    EXIT_CHAR => CellType::Exit,
+                    AGENT_CHAR => {
+                        agents.push((x, y));
+                        CellType::Empty
+                    }
+                    SPAWN_CHAR => {
+                        spawn_cells.push((x, y));
+                        CellType::Empty
+                    }
+                    _ => return Err(MapError::UnknownChar { line: first + y + 1, column: x + 1, found: c }),
+                };
+                row.push(cell);
+            }
+            cells.push(row);
+        }
+
+        Ok(MapLayout {
+            grid: Grid::from_cells(cells),
+            agents,
+            spawn_cells,
+        })
+    }
+
+    /// Réécrit le plan au format texte (inverse de `parse`)
+    pub fn to_ascii(&self) -> String {
+        let mut chars = grid_chars(&self.grid);
+        for &(x, y) in &self.spawn_cells {
+            if self.grid.is_empty(x, y) {
+                chars[y][x] = SPAWN_CHAR;
+            }
+        }
+        for &(x, y) in &self.agents {
+            chars[y][x] = AGENT_CHAR;
+        }
+        join_rows(&chars)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MapError> {
+        fs::write(path, self.to_ascii())?;
+        Ok(())
+    }
+}
+
+/// N'importe quelle grille peut être réécrite comme plan
+/// (les cellules occupées par des agents deviennent des `A`)
+impl From<Grid> for MapLayout {
+    fn from(grid: Grid) -> Self {
+        MapLayout {
+            grid,
+            agents: Vec::new(),
+            spawn_cells: Vec::new(),
+        }
+    }
+}
+
+fn grid_chars(grid: &Grid) -> Vec<Vec<char>> {
+    (0..grid.height())
+        .map(|y| {
+            (0..grid.width())
+                .map(|x| match grid.get(x, y) {
+                    Some(CellType::Wall) => WALL_CHAR,
+                    Some(CellType::Exit) => EXIT_CHAR,
+                    Some(CellType::Agent) => AGENT_CHAR,
+                    _ => EMPTY_CHAR,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn join_rows(chars: &[Vec<char>]) -> String {
+    let mut out = String::new();
+    for row in chars {
+        out.extend(row.iter());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_cells_agents_and_spawn_zone() {
+        let layout = MapLayout::parse("#####\n#AS.E\n#####\n").unwrap();
+        assert_eq!((layout.grid.width(), layout.grid.height()), (5, 3));
+        assert_eq!(layout.grid.get(0, 0), Some(CellType::Wall));
+        assert_eq!(layout.grid.get(4, 1), Some(CellType::Exit));
+        assert_eq!(layout.grid.get(1, 1), Some(CellType::Empty));
+        assert_eq!(layout.agents, vec![(1, 1)]);
+        assert_eq!(layout.spawn_cells, vec![(2, 1)]);
+    }
+
+    #[test]
+    fn parse_ignores_blank_lines_around_the_map() {
+        let layout = MapLayout::parse("\n  \n###\n#.E\n###\n\n").unwrap();
+        assert_eq!((layout.grid.width(), layout.grid.height()), (3, 3));
+    }
+
+    #[test]
+    fn parse_errors_give_file_line_numbers() {
+        assert!(matches!(
+            MapLayout::parse("\n###\n#.#E\n###"),
+            Err(MapError::RaggedLine { line: 3, expected: 3, found: 4 })
+        ));
+        assert!(matches!(
+            MapLayout::parse("###\n#?E\n###"),
+            Err(MapError::UnknownChar { line: 2, column: 2, found: '?' })
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_empty_and_exitless_maps() {
+        assert!(matches!(MapLayout::parse("\n \n"), Err(MapError::Empty)));
+        assert!(matches!(MapLayout::parse("###\n#.#\n###"), Err(MapError::NoExit)));
+        assert!(MapLayout::parse_plan("###\n#.#\n###").is_ok());
+    }
+}
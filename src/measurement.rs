@@ -0,0 +1,349 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::grid::CellType;
+use crate::neighbourhood::Lattice;
+use crate::recorder::StepObserver;
+use crate::simulation::Simulation;
+
+/// Dispositif de mesure du diagramme fondamental, décrit dans le scénario
+/// (section `[measurement]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MeasurementConfig {
+    /// Largeur d'une cellule en mètres (côté du carré ou largeur de l'hexagone)
+    pub cell_size: f32,
+    /// Durée d'une étape en secondes (0.4 m / 0.3 s ≈ 1.33 m/s en vitesse libre)
+    pub step_duration: f32,
+    /// Nombre d'étapes par fenêtre de mesure
+    pub window: usize,
+    pub areas: Vec<MeasurementArea>,
+    pub lines: Vec<MeasurementLine>,
+}
+
+impl Default for MeasurementConfig {
+    fn default() -> Self {
+        MeasurementConfig {
+            cell_size: 0.4,
+            step_duration: 0.3,
+            window: 10,
+            areas: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+}
+
+impl MeasurementConfig {
+    pub fn is_empty(&self) -> bool {
+        self.areas.is_empty() && self.lines.is_empty()
+    }
+}
+
+/// Zone rectangulaire de cellules, bornes incluses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MeasurementArea {
+    pub name: String,
+    pub min: [usize; 2],
+    pub max: [usize; 2],
+}
+
+impl MeasurementArea {
+    fn contains(&self, x: usize, y: usize) -> bool {
+        (self.min[0]..=self.max[0]).contains(&x) && (self.min[1]..=self.max[1]).contains(&y)
+    }
+}
+
+/// Segment de mesure en coordonnées de grille continues : les bords des
+/// cellules sont aux valeurs entières, le centre de la cellule (x, y) en
+/// (x + 0.5, y + 0.5) sur une grille carrée (voir `Lattice::centre` en
+/// hexagonal). Un passage compte +1 dans le sens de la normale
+/// (dy, -dx) du segment `from` → `to` (vers +x pour un segment tracé de haut
+/// en bas), -1 en sens inverse.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MeasurementLine {
+    pub name: String,
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+}
+
+impl MeasurementLine {
+    /// Longueur en cellules
+    fn length(&self) -> f32 {
+        (self.to[0] - self.from[0]).hypot(self.to[1] - self.from[1])
+    }
+
+    /// +1, -1 ou 0 selon que le déplacement (centre à centre) coupe le segment
+    fn crossing(&self, lattice: Lattice, (x0, y0): (usize, usize), (x1, y1): (usize, usize)) -> i32 {
+        let p = lattice.centre(x0, y0);
+        let q = lattice.centre(x1, y1);
+        let a = (self.from[0], self.from[1]);
+        let b = (self.to[0], self.to[1]);
+
+        let side = |o: (f32, f32), u: (f32, f32), v: (f32, f32)| {
+            (u.0 - o.0) * (v.1 - o.1) - (u.1 - o.1) * (v.0 - o.0)
+        };
+        let (p_side, q_side) = (side(a, b, p), side(a, b, q));
+        let (a_side, b_side) = (side(p, q, a), side(p, q, b));
+
+        // Coupure stricte du segment de ligne ; un centre posé sur la ligne compte d'un seul côté
+        let crosses_line = (p_side < 0.0) != (q_side < 0.0);
+        let crosses_move = a_side * b_side <= 0.0;
+        if !crosses_line || !crosses_move {
+            return 0;
+        }
+        // `side` négatif : du côté de la normale (dy, -dx)
+        if p_side >= 0.0 { 1 } else { -1 }
+    }
+}
+
+/// Un point du diagramme fondamental, mesuré sur une fenêtre d'étapes
+#[derive(Debug, Clone, Serialize)]
+pub struct MeasurementSample {
+    /// `area` ou `line`
+    pub kind: &'static str,
+    pub name: String,
+    /// Étapes couvertes (première exclue, dernière incluse)
+    pub start_step: usize,
+    pub end_step: usize,
+    /// Densité moyenne (personnes/m²), zones seulement
+    pub density: Option<f32>,
+    /// Vitesse moyenne des agents présents dans la zone (m/s), zones seulement
+    pub speed: Option<f32>,
+    /// Débit net à travers la ligne (personnes/s), lignes seulement
+    pub flow: Option<f32>,
+    /// Débit spécifique (personnes/m/s) : densité × vitesse pour une zone,
+    /// débit / longueur pour une ligne
+    pub specific_flow: Option<f32>,
+}
+
+/// Cumul d'une zone sur la fenêtre en cours
+#[derive(Debug, Clone, Default)]
+struct AreaWindow {
+    agent_steps: usize,
+    speed_sum: f32,
+    speed_count: usize,
+}
+
+/// Accumule les mesures au fil d'une exécution
+#[derive(Debug, Clone)]
+pub struct MeasurementRecorder {
+    config: MeasurementConfig,
+    /// Surface praticable de chaque zone, en m²
+    area_sizes: Vec<f32>,
+    areas: Vec<AreaWindow>,
+    crossings: Vec<i32>,
+    window_start: usize,
+    window_steps: usize,
+    observer: StepObserver,
+    /// Dernière étape observée
+    last_step: usize,
+    samples: Vec<MeasurementSample>,
+}
+
+impl MeasurementRecorder {
+    /// Démarre la mesure sur l'état courant (les positions servent de
+    /// référence pour les vitesses et les passages de la première étape)
+    pub fn new(config: MeasurementConfig, simulation: &Simulation) -> Self {
+        let grid = simulation.grid();
+        let cell_area = config.cell_size * config.cell_size * simulation.lattice().cell_area();
+        let area_sizes = config
+            .areas
+            .iter()
+            .map(|area| {
+                let cells = (area.min[1]..=area.max[1])
+                    .flat_map(|y| (area.min[0]..=area.max[0]).map(move |x| (x, y)))
+                    .filter(|&(x, y)| matches!(grid.get(x, y), Some(cell) if cell != CellType::Wall))
+                    .count();
+                cells as f32 * cell_area
+            })
+            .collect();
+
+        let mut recorder = MeasurementRecorder {
+            areas: vec![AreaWindow::default(); config.areas.len()],
+            crossings: vec![0; config.lines.len()],
+            config,
+            area_sizes,
+            window_start: simulation.step_count(),
+            window_steps: 0,
+            observer: StepObserver::default(),
+            last_step: simulation.step_count(),
+            samples: Vec::new(),
+        };
+        recorder.observer.observe(simulation);
+        recorder
+    }
+
+    /// Ajoute la dernière étape jouée ; une fenêtre complète produit un point par zone et par ligne
+    pub fn record(&mut self, simulation: &Simulation) {
+        // Positions après l'étape, agents sortis pendant l'étape compris (sur leur sortie)
+        let Some(observation) = self.observer.observe(simulation) else {
+            return;
+        };
+        self.last_step = observation.step;
+
+        let metres_per_second = self.config.cell_size / self.config.step_duration;
+        let lattice = simulation.lattice();
+        for agent in &observation.agents {
+            let (x, y) = agent.position;
+
+            // Un agent encombrant compte pour chaque cellule couverte dans la zone
+            for (area, window) in self.config.areas.iter().zip(self.areas.iter_mut()) {
+                let inside = agent.footprint.cells(x, y).filter(|&(cx, cy)| area.contains(cx, cy)).count();
+                if inside == 0 {
+                    continue;
+                }
+                window.agent_steps += inside;
+                if let Some((px, py)) = agent.previous {
+                    let ((cx, cy), (pcx, pcy)) = (lattice.centre(x, y), lattice.centre(px, py));
+                    let cells = (cx - pcx).hypot(cy - pcy);
+                    window.speed_sum += cells * metres_per_second * inside as f32;
+                    window.speed_count += inside;
+                }
+            }
+
+            if let Some(previous) = agent.previous {
+                for (line, crossings) in self.config.lines.iter().zip(self.crossings.iter_mut()) {
+                    *crossings += line.crossing(lattice, previous, (x, y));
+                }
+            }
+        }
+
+        self.window_steps += 1;
+        if self.window_steps >= self.config.window.max(1) {
+            self.flush(observation.step);
+        }
+    }
+
+    /// Ferme la fenêtre en cours (même incomplète) ; à appeler en fin d'exécution
+    pub fn finish(&mut self) {
+        if self.window_steps > 0 {
+            self.flush(self.last_step);
+        }
+    }
+
+    fn flush(&mut self, end_step: usize) {
+        let duration = self.window_steps as f32 * self.config.step_duration;
+
+        for ((area, window), &size) in self.config.areas.iter().zip(&self.areas).zip(&self.area_sizes) {
+            let density = (size > 0.0).then(|| window.agent_steps as f32 / self.window_steps as f32 / size);
+            let speed = (window.speed_count > 0).then(|| window.speed_sum / window.speed_count as f32);
+            self.samples.push(MeasurementSample {
+                kind: "area",
+                name: area.name.clone(),
+                start_step: self.window_start,
+                end_step,
+                density,
+                speed,
+                flow: None,
+                specific_flow: density.zip(speed).map(|(density, speed)| density * speed),
+            });
+        }
+
+        for (line, &crossings) in self.config.lines.iter().zip(&self.crossings) {
+            let flow = crossings as f32 / duration;
+            let length = line.length() * self.config.cell_size;
+            self.samples.push(MeasurementSample {
+                kind: "line",
+                name: line.name.clone(),
+                start_step: self.window_start,
+                end_step,
+                density: None,
+                speed: None,
+                flow: Some(flow),
+                specific_flow: (length > 0.0).then(|| flow / length),
+            });
+        }
+
+        self.areas = vec![AreaWindow::default(); self.config.areas.len()];
+        self.crossings = vec![0; self.config.lines.len()];
+        self.window_start = end_step;
+        self.window_steps = 0;
+    }
+
+    pub fn samples(&self) -> &[MeasurementSample] {
+        &self.samples
+    }
+
+    /// Une ligne par fenêtre et par zone ou ligne ; champs vides quand la grandeur
+    /// ne s'applique pas ou n'a pas pu être mesurée (zone vide)
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "kind,name,start_step,end_step,start_time,end_time,density,speed,flow,specific_flow")?;
+        let value = |v: Option<f32>| v.map(|v| format!("{:.4}", v)).unwrap_or_default();
+        for sample in &self.samples {
+            writeln!(
+                out,
+                "{},{},{},{},{:.2},{:.2},{},{},{},{}",
+                sample.kind,
+                sample.name,
+                sample.start_step,
+                sample.end_step,
+                sample.start_step as f32 * self.config.step_duration,
+                sample.end_step as f32 * self.config.step_duration,
+                value(sample.density),
+                value(sample.speed),
+                value(sample.flow),
+                value(sample.specific_flow)
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: impl Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(out, &self.samples).map_err(io::Error::from)
+    }
+
+    /// Écrit les points en JSON si le fichier finit par `.json`, en CSV sinon
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut out = BufWriter::new(File::create(path)?);
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            self.write_json(&mut out)?;
+        } else {
+            self.write_csv(&mut out)?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Topology;
+    use crate::neighbourhood::Neighbourhood;
+
+    fn vertical_line() -> MeasurementLine {
+        // Segment tracé de haut en bas en x = 5 : normale vers +x
+        MeasurementLine { name: "ligne".to_string(), from: [5.0, 0.0], to: [5.0, 10.0] }
+    }
+
+    fn square() -> Lattice {
+        Lattice::new(Topology::Square, Neighbourhood::Moore)
+    }
+
+    #[test]
+    fn crossing_sign_follows_the_normal() {
+        let line = vertical_line();
+        assert_eq!(line.crossing(square(), (4, 3), (5, 3)), 1);
+        assert_eq!(line.crossing(square(), (5, 3), (4, 3)), -1);
+        assert_eq!(line.crossing(square(), (4, 3), (5, 4)), 1);
+    }
+
+    #[test]
+    fn moves_that_do_not_cut_the_segment_do_not_count() {
+        let line = vertical_line();
+        assert_eq!(line.crossing(square(), (3, 3), (4, 3)), 0);
+        assert_eq!(line.crossing(square(), (4, 3), (4, 4)), 0);
+        // Au-delà de l'extrémité du segment
+        assert_eq!(line.crossing(square(), (4, 12), (5, 12)), 0);
+    }
+
+    #[test]
+    fn reversed_segment_reverses_the_sign() {
+        let line = MeasurementLine { name: "retour".to_string(), from: [5.0, 10.0], to: [5.0, 0.0] };
+        assert_eq!(line.crossing(square(), (4, 3), (5, 3)), -1);
+        assert_eq!(line.crossing(square(), (5, 3), (4, 3)), 1);
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use crate::grid::Topology;
+
+/// Cellules atteignables en un pas : le même voisinage sert au calcul du
+/// champ statique et au choix de déplacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Neighbourhood {
+    /// 4 voisins cardinaux
+    VonNeumann,
+    /// 8 voisins, diagonales comprises
+    #[default]
+    Moore,
+    /// Toutes les cellules à au plus `r` cellules dans chaque direction
+    /// (Moore de rayon r) : un agent parcourt jusqu'à r cellules par étape
+    Extended(#[serde(deserialize_with = "deserialize_radius")] usize),
+}
+
+pub const RADIUS_ERROR: &str = "le rayon d'un voisinage étendu doit valoir au moins 1";
+
+/// Rayon d'un voisinage étendu lu dans un scénario : 0 est refusé
+fn deserialize_radius<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    match usize::deserialize(deserializer)? {
+        0 => Err(D::Error::custom(RADIUS_ERROR)),
+        radius => Ok(radius),
+    }
+}
+
+impl Neighbourhood {
+    /// Déplacements (dx, dy) possibles : directions cardinales d'abord, puis
+    /// diagonales, puis anneaux plus lointains
+    pub fn offsets(self) -> Vec<(i32, i32)> {
+        let mut offsets = vec![
+            (0, -1),   // Haut
+            (1,  0),   // Droite
+            (0,  1),   // Bas
+            (-1, 0),   // Gauche
+        ];
+        if self == Neighbourhood::VonNeumann {
+            return offsets;
+        }
+        offsets.extend([
+            (1, -1),   // Haut-Droite
+            (1,  1),   // Bas-Droite
+            (-1, 1),   // Bas-Gauche
+            (-1,-1),   // Haut-Gauche
+        ]);
+        if let Neighbourhood::Extended(radius) = self {
+            for ring in 2..=radius as i32 {
+                for dy in -ring..=ring {
+                    for dx in -ring..=ring {
+                        if dx.abs().max(dy.abs()) == ring {
+                            offsets.push((dx, dy));
+                        }
+                    }
+                }
+            }
+        }
+        offsets
+    }
+}
+
+/// Voisinage posé sur le pavage d'une grille : cellules voisines, longueur
+/// des pas et centres des cellules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lattice {
+    pub topology: Topology,
+    pub neighbourhood: Neighbourhood,
+}
+
+impl Lattice {
+    pub fn new(topology: Topology, neighbourhood: Neighbourhood) -> Self {
+        Lattice { topology, neighbourhood }
+    }
+
+    /// Rayon du voisinage hexagonal : 1 pour von Neumann et Moore (les 6
+    /// hexagones adjacents), r pour un voisinage étendu
+    fn hex_radius(self) -> i32 {
+        match self.neighbourhood {
+            Neighbourhood::Extended(radius) => radius.max(1) as i32,
+            _ => 1,
+        }
+    }
+
+    /// Cellules voisines de (x, y), coordonnées négatives écartées
+    pub fn neighbours(self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let offsets: Vec<(i32, i32)> = match self.topology {
+            Topology::Square => self.neighbourhood.offsets(),
+            Topology::Hexagonal => {
+                // Décalages en coordonnées axiales, anneau par anneau
+                let radius = self.hex_radius();
+                let mut axial = Vec::new();
+                for dr in -radius..=radius {
+                    for dq in -radius..=radius {
+                        if (dq, dr) != (0, 0) && (dq + dr).abs() <= radius {
+                            axial.push((dq, dr));
+                        }
+                    }
+                }
+                axial.sort_by_key(|&(dq, dr)| hex_distance((dq, dr)));
+                let (q, r) = to_axial(x, y);
+                return axial
+                    .into_iter()
+                    .filter_map(|(dq, dr)| from_axial(q + dq, r + dr))
+                    .collect();
+            }
+        };
+        offsets
+            .into_iter()
+            .filter_map(|(dx, dy)| {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                (nx >= 0 && ny >= 0).then_some((nx as usize, ny as usize))
+            })
+            .collect()
+    }
+
+    /// Centre de la cellule (x, y) en largeurs de cellule. En hexagonal
+    /// (coordonnées décalées « odd-r »), les lignes impaires sont décalées
+    /// d'une demi-cellule vers la droite et les lignes espacées de sqrt(3)/2.
+    pub fn centre(self, x: usize, y: usize) -> (f32, f32) {
+        match self.topology {
+            Topology::Square => (x as f32 + 0.5, y as f32 + 0.5),
+            Topology::Hexagonal => (
+                x as f32 + 0.5 + 0.5 * (y % 2) as f32,
+                0.5 + y as f32 * 3f32.sqrt() / 2.0,
+            ),
+        }
+    }
+
+    /// Aire d'une cellule en largeurs de cellule au carré : un hexagone de
+    /// largeur 1 couvre sqrt(3)/2, l'écart entre deux lignes
+    pub fn cell_area(self) -> f32 {
+        match self.topology {
+            Topology::Square => 1.0,
+            Topology::Hexagonal => 3f32.sqrt() / 2.0,
+        }
+    }
+
+    /// Longueur euclidienne d'un pas, en cellules (1 entre hexagones adjacents)
+    pub fn step_length(self, (x0, y0): (usize, usize), (x1, y1): (usize, usize)) -> f32 {
+        match self.topology {
+            Topology::Square => {
+                let (dx, dy) = (x1 as i32 - x0 as i32, y1 as i32 - y0 as i32);
+                ((dx * dx + dy * dy) as f32).sqrt()
+            }
+            Topology::Hexagonal => {
+                let (q0, r0) = to_axial(x0, y0);
+                let (q1, r1) = to_axial(x1, y1);
+                let (dq, dr) = (q1 - q0, r1 - r0);
+                ((dq * dq + dq * dr + dr * dr) as f32).sqrt()
+            }
+        }
+    }
+
+    /// Cellules survolées par un pas de plus d'une cellule, extrémités
+    /// exclues : elles doivent être praticables pour que le pas le soit
+    pub fn crossed_cells(self, (x0, y0): (usize, usize), (x1, y1): (usize, usize)) -> Vec<(usize, usize)> {
+        match self.topology {
+            Topology::Square => {
+                let (dx, dy) = (x1 as i32 - x0 as i32, y1 as i32 - y0 as i32);
+                let n = dx.abs().max(dy.abs());
+                (1..n)
+                    .map(|t| {
+                        let cx = x0 as f32 + (dx * t) as f32 / n as f32;
+                        let cy = y0 as f32 + (dy * t) as f32 / n as f32;
+                        (cx.round() as usize, cy.round() as usize)
+                    })
+                    .collect()
+            }
+            Topology::Hexagonal => {
+                // Interpolation en coordonnées cubiques puis arrondi à l'hexagone le plus proche
+                let (q0, r0) = to_axial(x0, y0);
+                let (q1, r1) = to_axial(x1, y1);
+                let n = hex_distance((q1 - q0, r1 - r0));
+                (1..n)
+                    .filter_map(|t| {
+                        let t = t as f32 / n as f32;
+                        // Léger décalage pour départager les points à égale distance
+                        let q = q0 as f32 + (q1 - q0) as f32 * t + 1e-4;
+                        let r = r0 as f32 + (r1 - r0) as f32 * t + 1e-4;
+                        let (q, r) = cube_round(q, r);
+                        from_axial(q, r)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Coordonnées axiales (q, r) de la cellule décalée (x, y)
+fn to_axial(x: usize, y: usize) -> (i32, i32) {
+    (x as i32 - (y / 2) as i32, y as i32)
+}
+
+/// Cellule décalée d'un hexagone axial, si ses coordonnées sont positives
+fn from_axial(q: i32, r: i32) -> Option<(usize, usize)> {
+    if r < 0 {
+        return None;
+    }
+    let x = q + r / 2;
+    (x >= 0).then_some((x as usize, r as usize))
+}
+
+fn hex_distance((dq, dr): (i32, i32)) -> i32 {
+    dq.abs().max(dr.abs()).max((dq + dr).abs())
+}
+
+fn cube_round(q: f32, r: f32) -> (i32, i32) {
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    (rq as i32, rr as i32)
+}
+
+/// Nom accepté par `--neighbourhood` : `von_neumann`, `moore` ou `extended:<rayon>`
+impl fmt::Display for Neighbourhood {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Neighbourhood::VonNeumann => write!(f, "von_neumann"),
+            Neighbourhood::Moore => write!(f, "moore"),
+            Neighbourhood::Extended(radius) => write!(f, "extended:{}", radius),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Model {
+        neighbourhood: Neighbourhood,
+    }
+
+    fn parse(text: &str) -> Result<Neighbourhood, toml::de::Error> {
+        toml::from_str::<Model>(text).map(|model| model.neighbourhood)
+    }
+
+    #[test]
+    fn extended_radius_must_be_positive() {
+        assert_eq!(parse("neighbourhood = { extended = 2 }").unwrap(), Neighbourhood::Extended(2));
+        assert!(parse("neighbourhood = { extended = 0 }").is_err());
+    }
+
+    #[test]
+    fn offsets_grow_with_the_radius() {
+        assert_eq!(Neighbourhood::VonNeumann.offsets().len(), 4);
+        assert_eq!(Neighbourhood::Moore.offsets().len(), 8);
+        assert_eq!(Neighbourhood::Extended(2).offsets().len(), 24);
+    }
+}
@@ -0,0 +1,86 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Catégorie de piéton, qui fixe la vitesse de marche souhaitée
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentProfile {
+    #[default]
+    Adult,
+    Child,
+    Elderly,
+    /// Personne à mobilité réduite
+    Impaired,
+}
+
+impl AgentProfile {
+    /// Vitesse de marche souhaitée par défaut (m/s)
+    pub fn default_speed(self) -> f32 {
+        match self {
+            AgentProfile::Adult => 1.34,
+            AgentProfile::Child => 1.1,
+            AgentProfile::Elderly => 0.9,
+            AgentProfile::Impaired => 0.6,
+        }
+    }
+
+    /// Nom utilisé dans les scénarios et les fichiers exportés
+    pub fn key(self) -> &'static str {
+        match self {
+            AgentProfile::Adult => "adult",
+            AgentProfile::Child => "child",
+            AgentProfile::Elderly => "elderly",
+            AgentProfile::Impaired => "impaired",
+        }
+    }
+
+    /// Libellé affiché dans les bilans
+    pub fn label(self) -> &'static str {
+        match self {
+            AgentProfile::Adult => "Adultes",
+            AgentProfile::Child => "Enfants",
+            AgentProfile::Elderly => "Personnes âgées",
+            AgentProfile::Impaired => "Mobilité réduite",
+        }
+    }
+}
+
+/// Part d'un profil dans la population (`[[population.profiles]]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileShare {
+    pub profile: AgentProfile,
+    /// Poids relatif du profil (les parts n'ont pas besoin de sommer à 1)
+    pub share: f32,
+    /// Vitesse souhaitée en m/s (valeur par défaut du profil si absente)
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+impl ProfileShare {
+    pub fn speed(&self) -> f32 {
+        self.speed.unwrap_or_else(|| self.profile.default_speed())
+    }
+}
+
+/// Probabilité d'avancer à chaque étape : un agent à `free_speed` avance
+/// d'une cellule par étape, un agent plus lent reste sur place le reste du temps
+pub fn move_probability(speed: f32, free_speed: f32) -> f32 {
+    (speed / free_speed.max(f32::EPSILON)).clamp(0.0, 1.0)
+}
+
+/// Tire un profil selon les parts du mélange (`None` si le mélange est vide)
+pub fn draw_profile<'a>(mix: &'a [ProfileShare], rng: &mut impl Rng) -> Option<&'a ProfileShare> {
+    let total: f32 = mix.iter().map(|share| share.share.max(0.0)).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut roll = rng.gen::<f32>() * total;
+    for share in mix {
+        roll -= share.share.max(0.0);
+        if roll <= 0.0 {
+            return Some(share);
+        }
+    }
+    mix.last()
+}
@@ -0,0 +1,135 @@
+use std::path::Path;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use crate::grid::{CellType, Grid};
+use crate::map::MapError;
+
+/// Association d'une couleur de pixel à un type de cellule
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PaletteEntry {
+    pub color: [u8; 3],
+    pub cell: CellType,
+}
+
+/// Paramètres de conversion d'un plan image en grille
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RasterOptions {
+    /// Chaque pixel prend le type de la couleur la plus proche de la palette
+    pub palette: Vec<PaletteEntry>,
+    /// Échelle du plan (mètres représentés par un pixel)
+    pub metres_per_pixel: f32,
+    /// Taille réelle d'une cellule (0.4 m dans le modèle de champ de plancher classique)
+    pub cell_size: f32,
+    /// Proportion minimale de pixels de mur pour qu'une cellule devienne un mur
+    pub wall_threshold: f32,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        RasterOptions {
+            palette: vec![
+                PaletteEntry { color: [0, 0, 0], cell: CellType::Wall },
+                PaletteEntry { color: [0, 255, 0], cell: CellType::Exit },
+                PaletteEntry { color: [255, 255, 255], cell: CellType::Empty },
+            ],
+            metres_per_pixel: 0.4,
+            cell_size: 0.4,
+            wall_threshold: 0.25,
+        }
+    }
+}
+
+impl RasterOptions {
+    /// Nombre de pixels (par côté) regroupés dans une cellule
+    pub fn pixels_per_cell(&self) -> u32 {
+        ((self.cell_size / self.metres_per_pixel).round() as u32).max(1)
+    }
+
+    fn classify(&self, pixel: [u8; 4]) -> CellType {
+        // Les pixels transparents sont considérés comme du sol
+        if pixel[3] < 128 {
+            return CellType::Empty;
+        }
+
+        self.palette
+            .iter()
+            .min_by_key(|entry| {
+                entry.color
+                    .iter()
+                    .zip(pixel.iter())
+                    .map(|(&a, &b)| (a as i32 - b as i32).pow(2))
+                    .sum::<i32>()
+            })
+            .map(|entry| entry.cell)
+            .unwrap_or(CellType::Empty)
+    }
+}
+
+impl Grid {
+    /// Construit une grille à partir d'un plan PNG/BMP
+    pub fn from_image(path: impl AsRef<Path>, options: &RasterOptions) -> Result<Self, MapError> {
+        let grid = Self::image_plan(path, options)?;
+        if !grid.has_exit() {
+            return Err(MapError::NoExit);
+        }
+        Ok(grid)
+    }
+
+    /// Plan PNG/BMP lu sans exiger de sortie : un scénario peut ajouter les siennes
+    pub(crate) fn image_plan(path: impl AsRef<Path>, options: &RasterOptions) -> Result<Self, MapError> {
+        let image = image::open(path)?.to_rgba8();
+        Self::rgba_plan(&image, options)
+    }
+
+    /// Chaque bloc de `pixels_per_cell` x `pixels_per_cell` pixels donne une cellule :
+    /// une sortie si le bloc contient au moins autant de pixels de sortie que de mur,
+    /// un mur si la part de pixels de mur atteint `wall_threshold`, du sol sinon
+    pub fn from_rgba(image: &RgbaImage, options: &RasterOptions) -> Result<Self, MapError> {
+        let grid = Self::rgba_plan(image, options)?;
+        if !grid.has_exit() {
+            return Err(MapError::NoExit);
+        }
+        Ok(grid)
+    }
+
+    fn rgba_plan(image: &RgbaImage, options: &RasterOptions) -> Result<Self, MapError> {
+        let block = options.pixels_per_cell();
+        let width = image.width().div_ceil(block) as usize;
+        let height = image.height().div_ceil(block) as usize;
+        if width == 0 || height == 0 {
+            return Err(MapError::Empty);
+        }
+
+        let mut cells = vec![vec![CellType::Empty; width]; height];
+
+        for (y, row) in cells.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let mut walls = 0;
+                let mut exits = 0;
+                let mut total = 0;
+
+                for py in (y as u32 * block)..((y as u32 + 1) * block).min(image.height()) {
+                    for px in (x as u32 * block)..((x as u32 + 1) * block).min(image.width()) {
+                        match options.classify(image.get_pixel(px, py).0) {
+                            CellType::Wall => walls += 1,
+                            CellType::Exit => exits += 1,
+                            _ => {}
+                        }
+                        total += 1;
+                    }
+                }
+
+                *cell = if exits > 0 && exits >= walls {
+                    CellType::Exit
+                } else if walls as f32 >= options.wall_threshold * total as f32 && walls > 0 {
+                    CellType::Wall
+                } else {
+                    CellType::Empty
+                };
+            }
+        }
+
+        Ok(Grid::from_cells(cells))
+    }
+}
@@ -0,0 +1,232 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use serde::Serialize;
+use crate::agent::{AgentRecord, Footprint};
+use crate::simulation::{Simulation, StepStats};
+
+/// Série temporelle d'une exécution : une mesure par étape, état initial compris.
+/// Sert à tracer la courbe d'évacuation cumulée et à mesurer les débits.
+#[derive(Debug, Clone, Serialize)]
+pub struct Recorder {
+    /// Noms des sorties, dans l'ordre des colonnes `evacuated`
+    pub exits: Vec<String>,
+    pub steps: Vec<StepStats>,
+}
+
+impl Recorder {
+    /// Démarre l'enregistrement avec l'état courant de la simulation
+    pub fn new(simulation: &Simulation) -> Self {
+        Recorder {
+            exits: simulation.exits().iter().map(|exit| exit.name.clone()).collect(),
+            steps: vec![simulation.last_step().clone()],
+        }
+    }
+
+    /// Ajoute la dernière étape jouée (à appeler après chaque `step`)
+    pub fn record(&mut self, simulation: &Simulation) {
+        let stats = simulation.last_step();
+        if self.steps.last().is_some_and(|last| last.step == stats.step) {
+            return;
+        }
+        self.steps.push(stats.clone());
+    }
+
+    /// Une ligne par étape ; `evacuated_total` est le cumul depuis le début
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        write!(out, "step,remaining,injected,evacuated,evacuated_total,moves,blocked_conflicts,mean_field")?;
+        for id in 1..=self.exits.len() {
+            write!(out, ",evacuated_exit_{}", id)?;
+        }
+        writeln!(out)?;
+
+        let mut total = 0;
+        for stats in &self.steps {
+            let evacuated: usize = stats.evacuated.iter().sum();
+            total += evacuated;
+            write!(
+                out,
+                "{},{},{},{},{},{},{},{:.4}",
+                stats.step,
+                stats.remaining,
+                stats.injected,
+                evacuated,
+                total,
+                stats.moves,
+                stats.blocked_conflicts,
+                stats.mean_field
+            )?;
+            for count in &stats.evacuated {
+                write!(out, ",{}", count)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: impl Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(out, self).map_err(io::Error::from)
+    }
+
+    /// Écrit la série en JSON si le fichier finit par `.json`, en CSV sinon
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut out = BufWriter::new(File::create(path)?);
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            self.write_json(&mut out)?;
+        } else {
+            self.write_csv(&mut out)?;
+        }
+        out.flush()
+    }
+}
+
+/// Position d'un agent à une étape : cellule en haut à gauche et
+/// encombrement (1x1 pour un piéton ordinaire)
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TrajectoryPoint {
+    pub id: usize,
+    pub step: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl TrajectoryPoint {
+    fn new(id: usize, step: usize, (x, y): (usize, usize), footprint: Footprint) -> Self {
+        TrajectoryPoint { id, step, x, y, width: footprint.width, height: footprint.height }
+    }
+}
+
+/// Trajectoires de tous les agents : une position par agent et par étape,
+/// la dernière étant la cellule de sortie pour les agents évacués
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrajectoryRecorder {
+    pub points: Vec<TrajectoryPoint>,
+    #[serde(skip)]
+    observer: StepObserver,
+}
+
+impl TrajectoryRecorder {
+    /// Démarre l'enregistrement avec les positions courantes
+    pub fn new(simulation: &Simulation) -> Self {
+        let mut recorder = TrajectoryRecorder::default();
+        recorder.record(simulation);
+        recorder
+    }
+
+    /// Ajoute les positions de la dernière étape jouée
+    pub fn record(&mut self, simulation: &Simulation) {
+        let Some(observation) = self.observer.observe(simulation) else {
+            return;
+        };
+        let mut points: Vec<TrajectoryPoint> = observation
+            .agents
+            .iter()
+            .map(|agent| TrajectoryPoint::new(agent.id, observation.step, agent.position, agent.footprint))
+            .collect();
+        points.sort_by_key(|point| point.id);
+        self.points.extend(points);
+    }
+
+    /// CSV `id,step,x,y,width,height` trié par agent puis par étape (coordonnées en cellules)
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        let mut points = self.points.clone();
+        points.sort_by_key(|point| (point.id, point.step));
+
+        writeln!(out, "id,step,x,y,width,height")?;
+        for point in points {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                point.id, point.step, point.x, point.y, point.width, point.height
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_csv(&mut out)?;
+        out.flush()
+    }
+}
+
+/// Agent vu lors d'une observation
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ObservedAgent {
+    pub id: usize,
+    pub footprint: Footprint,
+    /// Cellule en haut à gauche, cellule de sortie pour un agent évacué
+    pub position: (usize, usize),
+    /// Position à l'observation précédente, `None` pour un agent apparu depuis
+    pub previous: Option<(usize, usize)>,
+    /// Vrai si l'agent est sorti depuis l'observation précédente
+    pub evacuated: bool,
+}
+
+/// État d'une étape pas encore observée
+#[derive(Debug, Clone)]
+pub(crate) struct Observation {
+    pub step: usize,
+    /// Agents sortis depuis l'observation précédente, puis agents présents
+    pub agents: Vec<ObservedAgent>,
+}
+
+/// Suivi commun aux enregistreurs : étape déjà vue, agents évacués déjà
+/// comptés et dernière position de chaque agent (indexée par identifiant)
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StepObserver {
+    last_step: Option<usize>,
+    evacuated_seen: usize,
+    previous: Vec<Option<(usize, usize)>>,
+}
+
+impl StepObserver {
+    /// `None` si l'étape courante a déjà été observée
+    pub fn observe(&mut self, simulation: &Simulation) -> Option<Observation> {
+        let step = simulation.step_count();
+        if self.last_step == Some(step) {
+            return None;
+        }
+        self.last_step = Some(step);
+
+        let evacuated = &simulation.evacuated_agents()[self.evacuated_seen..];
+        self.evacuated_seen += evacuated.len();
+        let seen = evacuated
+            .iter()
+            .map(|record| (record.id, record.footprint, (record.x, record.y), true))
+            .chain(simulation.agents().iter().map(|agent| (agent.id, agent.footprint, (agent.x, agent.y), false)));
+
+        let mut agents = Vec::new();
+        for (id, footprint, position, evacuated) in seen {
+            if id >= self.previous.len() {
+                self.previous.resize(id + 1, None);
+            }
+            let previous = std::mem::replace(&mut self.previous[id], (!evacuated).then_some(position));
+            agents.push(ObservedAgent { id, footprint, position, previous, evacuated });
+        }
+        Some(Observation { step, agents })
+    }
+}
+
+/// Bilans individuels : `exit` est le numéro de la sortie (1 pour « Sortie 1 »),
+/// vide ainsi que `exit_step` pour un agent encore dans la salle
+pub fn write_agent_records_csv(records: &[AgentRecord], mut out: impl Write) -> io::Result<()> {
+    writeln!(out, "id,profile,spawn_step,exit_step,exit,waiting_steps,path_length")?;
+    for record in records {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{:.3}",
+            record.id,
+            record.profile.key(),
+            record.spawn_step,
+            record.exit_step.map(|step| step.to_string()).unwrap_or_default(),
+            record.exit.map(|exit| (exit + 1).to_string()).unwrap_or_default(),
+            record.waiting_steps,
+            record.path_length
+        )?;
+    }
+    Ok(())
+}
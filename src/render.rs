@@ -0,0 +1,74 @@
+use macroquad::prelude::*;
+use crate::grid::{CellType, Grid, Topology};
+use crate::heatmap::{HeatmapLayer, Heatmaps};
+use crate::neighbourhood::{Lattice, Neighbourhood};
+use crate::simulation::Simulation;
+
+/// Dessine la cellule (x, y) : un carré, ou un hexagone pointe en haut
+/// dont la largeur vaut `cell_size`
+fn draw_cell(topology: Topology, x: usize, y: usize, cell_size: f32, color: Color, border: Option<Color>) {
+    match topology {
+        Topology::Square => {
+            let (px, py) = (x as f32 * cell_size, y as f32 * cell_size);
+            draw_rectangle(px, py, cell_size, cell_size, color);
+            if let Some(border) = border {
+                draw_rectangle_lines(px, py, cell_size, cell_size, 0.5, border);
+            }
+        }
+        Topology::Hexagonal => {
+            let (cx, cy) = Lattice::new(topology, Neighbourhood::Moore).centre(x, y);
+            let radius = cell_size / 3f32.sqrt();
+            let (thickness, border) = match border {
+                Some(border) => (0.5, border),
+                None => (0.0, color),
+            };
+            draw_hexagon(cx * cell_size, cy * cell_size, radius, thickness, true, border, color);
+        }
+    }
+}
+
+impl Grid {
+    pub fn draw(&self, cell_size: f32) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let color = match self.get(x, y) {
+                    Some(CellType::Wall) => Color::new(0.2, 0.2, 0.2, 1.0),
+                    Some(CellType::Agent) => Color::new(0.2, 0.5, 0.9, 1.0),
+                    Some(CellType::Exit) => Color::new(0.2, 0.8, 0.2, 1.0),
+                    _ => Color::new(0.95, 0.95, 0.95, 1.0),
+                };
+
+                draw_cell(self.topology(), x, y, cell_size, color, Some(Color::new(0.8, 0.8, 0.8, 1.0)));
+            }
+        }
+    }
+}
+
+impl Simulation {
+    pub fn draw(&self, cell_size: f32) {
+        self.grid().draw(cell_size);
+    }
+}
+
+impl Heatmaps {
+    /// Superpose une carte à la grille : du jaune transparent (faible)
+    /// au rouge opaque (valeur maximale de la carte)
+    pub fn draw(&self, layer: HeatmapLayer, cell_size: f32) {
+        let values = self.layer(layer);
+        let max = values.iter().flatten().cloned().fold(0.0, f32::max);
+        if max <= 0.0 {
+            return;
+        }
+
+        for (y, row) in values.iter().enumerate() {
+            for (x, &value) in row.iter().enumerate() {
+                if value <= 0.0 {
+                    continue;
+                }
+                let t = value / max;
+                let color = Color::new(1.0, 1.0 - t, 0.0, 0.25 + 0.6 * t);
+                draw_cell(self.topology(), x, y, cell_size, color, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::exits::ExitStrategy;
+use crate::floor_field::FieldMethod;
+use crate::grid::{CellType, Grid, ObstaclePattern, Topology};
+use crate::map::{MapError, MapLayout};
+use crate::profile::ProfileShare;
+use crate::measurement::MeasurementConfig;
+use crate::neighbourhood::Neighbourhood;
+use crate::raster::RasterOptions;
+use crate::source::SourceConfig;
+use crate::spawn::{LargeAgentGroup, PlacementError, SpawnGroup, SpawnZone};
+
+/// Description complète d'une expérience : salle, population, paramètres
+/// du modèle, graine aléatoire et conditions d'arrêt.
+/// Se lit depuis un fichier JSON ou TOML.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub grid: GridSource,
+    /// Pavage carré (par défaut) ou hexagonal ; le plan est relu tel quel
+    #[serde(default)]
+    pub topology: Topology,
+    /// Cellules de sortie ajoutées au plan
+    #[serde(default)]
+    pub exits: Vec<[usize; 2]>,
+    #[serde(default)]
+    pub population: Population,
+    /// Zones d'injection d'agents au fil du temps
+    #[serde(default)]
+    pub sources: Vec<SourceConfig>,
+    #[serde(default)]
+    pub model: ModelParams,
+    /// Graine du générateur aléatoire (tirée au hasard si absente)
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub stop: StopConditions,
+    /// Zones et lignes de mesure du diagramme fondamental
+    #[serde(default)]
+    pub measurement: MeasurementConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GridSource {
+    /// Une des salles prédéfinies
+    Pattern {
+        #[serde(default = "default_width")]
+        width: usize,
+        #[serde(default = "default_height")]
+        height: usize,
+        pattern: ObstaclePattern,
+    },
+    /// Plan ASCII (voir `map.rs`)
+    Ascii { path: PathBuf },
+    /// Plan PNG/BMP (voir `raster.rs`)
+    Image {
+        path: PathBuf,
+        #[serde(flatten)]
+        options: RasterOptions,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Population {
+    /// Nombre d'agents placés au hasard (zone `S` du plan, sinon toute la salle)
+    pub agents: usize,
+    /// Positions explicites, en plus des agents placés au hasard
+    pub placements: Vec<[usize; 2]>,
+    /// Zones nommées, remplies avant le tirage de `agents`
+    pub zones: Vec<SpawnZone>,
+    /// Côté d'une cellule en mètres, pour les densités des zones
+    pub cell_size: f32,
+    /// Mélange de profils (tous adultes si vide)
+    pub profiles: Vec<ProfileShare>,
+    /// Agents encombrants sur plusieurs cellules, tirés avant les zones
+    pub large_agents: Vec<LargeAgentGroup>,
+}
+
+impl Default for Population {
+    fn default() -> Self {
+        Population {
+            agents: 0,
+            placements: Vec::new(),
+            zones: Vec::new(),
+            cell_size: 0.4,
+            profiles: Vec::new(),
+            large_agents: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelParams {
+    pub k_s: f32,
+    pub use_probabilistic: bool,
+    /// Poids du champ de murs ajouté au champ statique (0 = distances pures)
+    pub wall_avoidance: f32,
+    pub field_method: FieldMethod,
+    /// Voisinage des déplacements et du champ statique (Dijkstra)
+    pub neighbourhood: Neighbourhood,
+    /// Couplage au champ dynamique (0 = champ dynamique ignoré)
+    pub k_d: f32,
+    /// Inertie : favorise les pas qui prolongent le déplacement précédent
+    /// (0 = aucune mémoire de direction)
+    pub k_i: f32,
+    /// Diffusion du champ dynamique
+    pub alpha: f32,
+    /// Décroissance du champ dynamique
+    pub delta: f32,
+    /// Recalcul du champ statique en tenant compte de la foule toutes les
+    /// `congestion_interval` étapes (jamais si absent)
+    pub congestion_interval: Option<usize>,
+    /// Surcoût d'une cellule occupée lors de ce recalcul
+    pub congestion_penalty: f32,
+    pub exit_strategy: ExitStrategy,
+    /// Réévaluation du choix de sortie toutes les `exit_reevaluation` étapes
+    /// (choix définitif si absent)
+    pub exit_reevaluation: Option<usize>,
+    /// Distance à la sortie en dessous de laquelle un agent est compté dans la file
+    pub queue_radius: f32,
+    /// Débit d'une cellule de sortie (agents par étape), pour le temps estimé
+    pub exit_flow: f32,
+    pub conflict_policy: ConflictPolicy,
+    /// Paramètre de friction mu (politique `friction`) : probabilité
+    /// qu'aucun agent ne bouge lors d'un conflit
+    pub friction: f32,
+    /// Vitesse (m/s) d'un agent qui avance d'une cellule à chaque étape ;
+    /// les profils plus lents avancent avec la probabilité vitesse / free_speed
+    pub free_speed: f32,
+}
+
+impl Default for ModelParams {
+    fn default() -> Self {
+        ModelParams {
+            k_s: 2.0,
+            use_probabilistic: false,
+            wall_avoidance: 0.0,
+            field_method: FieldMethod::Dijkstra,
+            neighbourhood: Neighbourhood::Moore,
+            k_d: 0.0,
+            k_i: 0.0,
+            alpha: 0.3,
+            delta: 0.3,
+            congestion_interval: None,
+            congestion_penalty: 3.0,
+            exit_strategy: ExitStrategy::Nearest,
+            exit_reevaluation: None,
+            queue_radius: 5.0,
+            exit_flow: 1.0,
+            conflict_policy: ConflictPolicy::NearestWins,
+            friction: 0.0,
+            free_speed: 1.34,
+        }
+    }
+}
+
+/// Résolution des conflits quand plusieurs agents visent la même cellule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Le plus proche de sa sortie passe
+    #[default]
+    NearestWins,
+    /// Gagnant tiré uniformément
+    UniformRandom,
+    /// Gagnant tiré selon la préférence de chaque agent pour la cellule
+    ProbabilityWeighted,
+    /// Friction mu (Kirchner et al.) : personne ne passe avec la probabilité
+    /// `friction`, sinon tirage pondéré par la préférence
+    Friction,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StopConditions {
+    /// Nombre maximal d'étapes (illimité si absent)
+    pub max_steps: Option<usize>,
+    /// La simulation s'arrête quand il reste au plus ce nombre d'agents
+    pub max_remaining: usize,
+}
+
+fn default_width() -> usize {
+    60
+}
+
+fn default_height() -> usize {
+    40
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    UnknownFormat(PathBuf),
+    Map(MapError),
+    Placement(PlacementError),
+    /// Salle prédéfinie plus petite que `ObstaclePattern::min_size`
+    GridTooSmall { width: usize, height: usize, min: (usize, usize) },
+    /// Cellule de `exits` hors de la grille
+    ExitOutOfGrid { x: usize, y: usize },
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(err) => write!(f, "erreur de lecture : {}", err),
+            ScenarioError::Json(err) => write!(f, "JSON invalide : {}", err),
+            ScenarioError::Toml(err) => write!(f, "TOML invalide : {}", err),
+            ScenarioError::UnknownFormat(path) => write!(
+                f,
+                "format de scénario inconnu pour {} (attendu .json ou .toml)",
+                path.display()
+            ),
+            ScenarioError::Map(err) => write!(f, "plan invalide : {}", err),
+            ScenarioError::Placement(err) => write!(f, "placement impossible : {}", err),
+            ScenarioError::GridTooSmall { width, height, min } => write!(
+                f,
+                "salle prédéfinie trop petite : {}x{} (au moins {}x{} pour ce motif)",
+                width, height, min.0, min.1
+            ),
+            ScenarioError::ExitOutOfGrid { x, y } => {
+                write!(f, "sortie ({}, {}) hors de la grille", x, y)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl From<io::Error> for ScenarioError {
+    fn from(err: io::Error) -> Self {
+        ScenarioError::Io(err)
+    }
+}
+
+impl From<MapError> for ScenarioError {
+    fn from(err: MapError) -> Self {
+        ScenarioError::Map(err)
+    }
+}
+
+impl From<PlacementError> for ScenarioError {
+    fn from(err: PlacementError) -> Self {
+        ScenarioError::Placement(err)
+    }
+}
+
+impl Scenario {
+    /// Lit un scénario `.json` ou `.toml`. Les chemins de plans sont
+    /// relatifs au dossier du fichier de scénario.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+
+        let mut scenario: Scenario = match extension.as_deref() {
+            Some("json") => serde_json::from_str(&text).map_err(ScenarioError::Json)?,
+            Some("toml") => toml::from_str(&text).map_err(ScenarioError::Toml)?,
+            _ => return Err(ScenarioError::UnknownFormat(path.to_path_buf())),
+        };
+
+        if let Some(base) = path.parent() {
+            scenario.grid.resolve_paths(base);
+        }
+        Ok(scenario)
+    }
+
+    /// Construit le plan décrit par le scénario (sorties et placements explicites compris)
+    pub fn build_layout(&self) -> Result<MapLayout, ScenarioError> {
+        let mut layout = match &self.grid {
+            GridSource::Pattern { width, height, pattern } => {
+                let min = pattern.min_size();
+                if *width < min.0 || *height < min.1 {
+                    return Err(ScenarioError::GridTooSmall { width: *width, height: *height, min });
+                }
+                MapLayout::from(Grid::new_with_pattern(*width, *height, *pattern))
+            }
+            GridSource::Ascii { path } => MapLayout::load_plan(path)?,
+            GridSource::Image { path, options } => MapLayout::from(Grid::image_plan(path, options)?),
+        };
+
+        layout.grid = layout.grid.with_topology(self.topology);
+        for &[x, y] in &self.exits {
+            if layout.grid.get(x, y).is_none() {
+                return Err(ScenarioError::ExitOutOfGrid { x, y });
+            }
+            layout.grid.set(x, y, CellType::Exit);
+        }
+        // Les sorties du scénario peuvent compléter un plan qui n'en a pas
+        if !layout.grid.has_exit() {
+            return Err(MapError::NoExit.into());
+        }
+        layout
+            .agents
+            .extend(self.population.placements.iter().map(|&[x, y]| (x, y)));
+
+        Ok(layout)
+    }
+
+    /// Groupes d'agents à tirer au hasard : les agents encombrants, les zones
+    /// nommées dans l'ordre, puis `population.agents` agents dans la zone `S` ou toute la salle
+    pub fn spawn_groups(&self, layout: &MapLayout) -> Result<Vec<SpawnGroup>, ScenarioError> {
+        let mut groups: Vec<SpawnGroup> = self
+            .population
+            .large_agents
+            .iter()
+            .map(|large| large.group(&layout.grid))
+            .collect();
+        for zone in &self.population.zones {
+            groups.push(zone.group(&layout.grid, self.population.cell_size)?);
+        }
+        groups.push(layout.random_group(self.population.agents));
+        Ok(groups)
+    }
+}
+
+impl GridSource {
+    fn resolve_paths(&mut self, base: &Path) {
+        match self {
+            GridSource::Ascii { path } | GridSource::Image { path, .. } => {
+                if path.is_relative() {
+                    *path = base.join(&*path);
+                }
+            }
+            GridSource::Pattern { .. } => {}
+        }
+    }
+}
@@ -1,165 +1,551 @@
-use crate::agent::Agent;
+use crate::agent::{Agent, AgentRecord, FieldCoupling, Footprint};
+use crate::dynamic_field::DynamicFloorField;
+use crate::exits::{Exit, ExitSet};
 use crate::floor_field::FloorField;
 use crate::grid::{CellType, Grid, ObstaclePattern};
+use crate::map::MapLayout;
+use crate::neighbourhood::Lattice;
+use crate::profile::{draw_profile, move_probability, AgentProfile, ProfileShare};
+use crate::scenario::{ConflictPolicy, ModelParams, Scenario, ScenarioError, StopConditions};
+use crate::source::Source;
+use crate::spawn::{PlacementError, SpawnGroup};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
-use std::collections::HashMap;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::collections::BTreeMap;
 
 pub struct Simulation {
     grid: Grid,
-    floor_field: FloorField,
+    exits: ExitSet,
+    /// Champs statiques (distances pures) : files d'attente, choix de la
+    /// sortie, priorité des conflits et statistiques
+    fields: FieldSet,
+    /// Champs avec la foule comme surcoût (`congestion_interval`) : une fois
+    /// calculés, ils remplacent les champs statiques pour choisir les déplacements
+    congested_fields: Option<FieldSet>,
+    dynamic_field: DynamicFloorField,
     agents: Vec<Agent>,
-    k_s: f32,
+    model: ModelParams,
     step_count: usize,
-    use_probabilistic: bool,
+    stop: StopConditions,
+    seed: u64,
+    /// Unique source d'aléa : placement, phases, choix de déplacement,
+    /// ordre de mise à jour et conflits
+    rng: StdRng,
+    initial_agent_count: usize,
+    evacuated_per_exit: Vec<usize>,
+    last_step: StepStats,
+    /// Bilans des agents sortis, dans l'ordre de sortie
+    evacuated_agents: Vec<AgentRecord>,
+    /// Sources qui injectent des agents au fil des étapes
+    sources: Vec<Source>,
+    injected_count: usize,
+    /// Prochain identifiant d'agent
+    next_agent_id: usize,
+    /// Mélange de profils des agents placés et injectés
+    profiles: Vec<ProfileShare>,
+}
+
+/// Mesures de la dernière étape (ou de l'état initial avant la première)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StepStats {
+    pub step: usize,
+    /// Agents encore dans la salle après l'étape
+    pub remaining: usize,
+    /// Agents injectés par les sources pendant l'étape
+    pub injected: usize,
+    /// Agents sortis pendant l'étape, par sortie
+    pub evacuated: Vec<usize>,
+    /// Agents qui ont changé de cellule
+    pub moves: usize,
+    /// Agents bloqués par un conflit perdu (ou par la friction)
+    pub blocked_conflicts: usize,
+    /// Valeur moyenne du champ statique sous les agents restants
+    /// (distance moyenne à leur sortie cible)
+    pub mean_field: f32,
+}
+
+/// Bilan d'une exécution
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub seed: u64,
+    pub steps: usize,
+    pub initial_agents: usize,
+    /// Agents ajoutés par les sources en cours d'exécution
+    pub injected_agents: usize,
+    pub evacuated: usize,
+    pub remaining: usize,
+    /// Agents évacués par étape
+    pub agents_per_step: f32,
+    /// Vrai si tous les agents sont sortis
+    pub completed: bool,
+    pub exits: Vec<ExitSummary>,
+    pub profiles: Vec<ProfileSummary>,
+}
+
+/// Statistiques d'un profil d'agents
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSummary {
+    pub profile: AgentProfile,
+    pub agents: usize,
+    pub evacuated: usize,
+    /// Étapes moyennes entre apparition et sortie (agents sortis)
+    pub mean_evacuation_time: Option<f32>,
+    pub mean_waiting_steps: f32,
+    pub mean_path_length: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitSummary {
+    pub name: String,
+    pub evacuated: usize,
 }
 
 impl Simulation {
     
+    /// Salle prédéfinie avec `num_agents` agents placés au hasard ; échoue
+    /// si la salle n'a pas assez de cellules libres
     pub fn new_with_pattern(
         width: usize, 
         height: usize, 
         num_agents: usize, 
         k_s: f32,
         pattern: ObstaclePattern
-    ) -> Self {
-        let mut grid = Grid::new_with_pattern(width, height, pattern);
-        let floor_field = FloorField::new(&grid);
-        
+    ) -> Result<Self, PlacementError> {
+        let layout = MapLayout::from(Grid::new_with_pattern(width, height, pattern));
+        Self::new_with_map(layout, num_agents, k_s)
+    }
+
+    /// Simulation sur un plan chargé depuis un fichier : les agents `A` sont
+    /// placés tels quels, puis `num_agents` agents supplémentaires sont tirés
+    /// dans la zone d'apparition `S` (ou partout si le plan n'en a pas)
+    pub fn new_with_map(layout: MapLayout, num_agents: usize, k_s: f32) -> Result<Self, PlacementError> {
+        let model = ModelParams { k_s, ..ModelParams::default() };
+        let groups = [layout.random_group(num_agents)];
+        Self::new_with_grid(layout.grid, &layout.agents, &groups, model, rand::random())
+    }
+
+    /// Simulation entièrement décrite par un fichier de scénario
+    pub fn from_scenario(scenario: &Scenario) -> Result<Self, ScenarioError> {
+        let layout = scenario.build_layout()?;
+        let seed = scenario.seed.unwrap_or_else(rand::random);
+        Self::from_layout(scenario, layout, seed)
+    }
+
+    /// Simulation d'un scénario sur un plan déjà construit (`Scenario::build_layout`),
+    /// avec une graine imposée : évite de relire le plan à chaque réplication
+    pub fn from_layout(scenario: &Scenario, layout: MapLayout, seed: u64) -> Result<Self, ScenarioError> {
+        let groups = scenario.spawn_groups(&layout)?;
+        let mut simulation = Self::new_with_grid(
+            layout.grid,
+            &layout.agents,
+            &groups,
+            scenario.model.clone(),
+            seed,
+        )?;
+        simulation.stop = scenario.stop.clone();
+        simulation.profiles = scenario.population.profiles.clone();
+        // Les agents encombrants ont le profil de leur groupe
+        for i in 0..simulation.agents.len() {
+            if simulation.agents[i].footprint.is_single() {
+                simulation.assign_profile(i);
+            }
+        }
+        simulation.sources = scenario
+            .sources
+            .iter()
+            .map(|config| Source::new(config.clone(), &simulation.grid))
+            .collect();
+        Ok(simulation)
+    }
+
+    /// Place les agents aux positions `fixed_agents`, puis tire ceux de chaque
+    /// groupe parmi ses cellules encore libres, dans l'ordre des groupes.
+    /// Un agent encombrant n'est placé que si tout son encombrement est libre.
+    fn new_with_grid(
+        mut grid: Grid,
+        fixed_agents: &[(usize, usize)],
+        groups: &[SpawnGroup],
+        model: ModelParams,
+        seed: u64,
+    ) -> Result<Self, PlacementError> {
+        let exits = ExitSet::from_grid(&grid);
+        let exit_fields: Vec<FloorField> = exits
+            .exits()
+            .iter()
+            .map(|exit| {
+                FloorField::new(&grid, &exit.cells, model.field_method, model.neighbourhood, model.wall_avoidance)
+            })
+            .collect();
+        let dynamic_field = DynamicFloorField::new(grid.width(), grid.height(), model.alpha, model.delta);
+
         let mut agents = Vec::new();
-        let mut rng = rand::thread_rng();
-        let mut placed = 0;
-        
-        while placed < num_agents {
-            let x = rng.gen_range(1..width - 1);
-            let y = rng.gen_range(1..height - 1);
-            
-            if grid.is_empty(x, y) {
-                let agent = Agent::new(x, y, placed);
-                grid.set(x, y, CellType::Agent);
-                agents.push(agent);
-                placed += 1;
+        // Tout le tirage aléatoire découle de la graine
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for &(x, y) in fixed_agents {
+            if !grid.is_empty(x, y) {
+                return Err(PlacementError::InvalidCell { x, y });
             }
+            agents.push(Agent::new(x, y, agents.len(), &mut rng));
+            grid.set(x, y, CellType::Agent);
         }
-        
-        Simulation {
+
+        for group in groups {
+            let first = agents.len();
+            if group.footprint.is_single() {
+                // Cellules libres candidates, tirées sans remise
+                let mut free_cells = group.cells.clone();
+                free_cells.retain(|&(x, y)| grid.is_empty(x, y));
+                if free_cells.len() < group.count {
+                    return Err(PlacementError::ZoneFull {
+                        zone: group.name.clone(),
+                        requested: group.count,
+                        available: free_cells.len(),
+                    });
+                }
+                free_cells.shuffle(&mut rng);
+
+                for (x, y) in free_cells.into_iter().take(group.count) {
+                    agents.push(Agent::new(x, y, agents.len(), &mut rng));
+                    grid.set(x, y, CellType::Agent);
+                }
+            } else {
+                // Les encombrements déjà posés peuvent recouvrir les positions suivantes
+                let fits = |grid: &Grid, x: usize, y: usize| {
+                    group.footprint.cells(x, y).all(|(cx, cy)| grid.is_empty(cx, cy))
+                };
+                let mut anchors = group.cells.clone();
+                anchors.retain(|&(x, y)| fits(&grid, x, y));
+                anchors.shuffle(&mut rng);
+
+                for (x, y) in anchors {
+                    if agents.len() - first == group.count {
+                        break;
+                    }
+                    if !fits(&grid, x, y) {
+                        continue;
+                    }
+                    let mut agent = Agent::new(x, y, agents.len(), &mut rng);
+                    agent.footprint = group.footprint;
+                    for (cx, cy) in agent.cells() {
+                        grid.set(cx, cy, CellType::Agent);
+                    }
+                    agents.push(agent);
+                }
+                if agents.len() - first < group.count {
+                    return Err(PlacementError::ZoneFull {
+                        zone: group.name.clone(),
+                        requested: group.count,
+                        available: agents.len() - first,
+                    });
+                }
+            }
+
+            if let Some((profile, speed)) = group.profile {
+                for agent in &mut agents[first..] {
+                    agent.profile = profile;
+                    agent.move_probability = move_probability(speed, model.free_speed);
+                }
+            }
+        }
+
+        let mut footprint_fields: Vec<(Footprint, Vec<FloorField>)> = Vec::new();
+        for agent in &agents {
+            if !agent.footprint.is_single() && !footprint_fields.iter().any(|(f, _)| *f == agent.footprint) {
+                footprint_fields.push((agent.footprint, Self::footprint_exit_fields(&grid, &exits, agent.footprint, &model, 0.0)));
+            }
+        }
+
+        let evacuated_per_exit = vec![0; exits.exits().len()];
+        let initial_agent_count = agents.len();
+        let mut simulation = Simulation {
             grid,
-            floor_field,
+            exits,
+            fields: FieldSet { exits: exit_fields, footprints: footprint_fields },
+            congested_fields: None,
+            dynamic_field,
             agents,
-            k_s,
+            model,
             step_count: 0,
-            use_probabilistic: false, 
+            stop: StopConditions::default(),
+            seed,
+            rng,
+            initial_agent_count,
+            evacuated_per_exit,
+            last_step: StepStats::default(),
+            evacuated_agents: Vec::new(),
+            sources: Vec::new(),
+            injected_count: 0,
+            next_agent_id: initial_agent_count,
+            profiles: Vec::new(),
+        };
+        simulation.assign_exits();
+        simulation.last_step = StepStats {
+            step: 0,
+            remaining: simulation.agents.len(),
+            injected: 0,
+            evacuated: vec![0; simulation.exits.exits().len()],
+            moves: 0,
+            blocked_conflicts: 0,
+            mean_field: simulation.mean_field_value(),
+        };
+        Ok(simulation)
+    }
+
+    /// Champs par sortie pour un encombrement : calculés sur la grille des
+    /// positions où il tient (`Grid::for_footprint`), ils évitent les passages
+    /// trop étroits ; la cible d'une sortie est toute position qui la recouvre.
+    /// `congestion_penalty` s'applique aux positions qui recouvrent un agent.
+    fn footprint_exit_fields(
+        grid: &Grid,
+        exits: &ExitSet,
+        footprint: Footprint,
+        model: &ModelParams,
+        congestion_penalty: f32,
+    ) -> Vec<FloorField> {
+        let positions = grid.for_footprint(footprint);
+        exits
+            .exits()
+            .iter()
+            .map(|exit| {
+                let targets: Vec<(usize, usize)> = (0..positions.height())
+                    .flat_map(|y| (0..positions.width()).map(move |x| (x, y)))
+                    .filter(|&(x, y)| {
+                        positions.is_exit(x, y) && footprint.cells(x, y).any(|cell| exit.cells.contains(&cell))
+                    })
+                    .collect();
+                FloorField::with_congestion(
+                    &positions,
+                    &targets,
+                    model.field_method,
+                    model.neighbourhood,
+                    model.wall_avoidance,
+                    congestion_penalty,
+                )
+            })
+            .collect()
+    }
+
+    /// Champs suivis par les agents de cet encombrement
+    fn fields_for(&self, footprint: Footprint) -> &[FloorField] {
+        self.fields.for_footprint(footprint)
+    }
+
+    /// (Ré)attribue une sortie cible à chaque agent selon la stratégie du modèle.
+    /// Les files d'attente sont mises à jour au fil des choix pour que les
+    /// agents ne se reportent pas tous en même temps sur la même sortie.
+    fn assign_exits(&mut self) {
+        let mut queues = self.queue_lengths();
+
+        for i in 0..self.agents.len() {
+            let agent = self.agents[i];
+            let fields = self.fields_for(agent.footprint);
+            let in_queue = |exit: usize| {
+                fields[exit].static_distances()[agent.y][agent.x] <= self.model.queue_radius
+            };
+
+            if let Some(exit) = self.exits.choose(
+                self.model.exit_strategy,
+                fields,
+                &queues,
+                self.model.exit_flow,
+                (agent.x, agent.y),
+            ) {
+                if exit != agent.target_exit {
+                    if in_queue(agent.target_exit) && queues[agent.target_exit] > 0 {
+                        queues[agent.target_exit] -= 1;
+                    }
+                    if in_queue(exit) {
+                        queues[exit] += 1;
+                    }
+                    self.agents[i].target_exit = exit;
+                }
+            }
+        }
+    }
+
+    /// Nombre d'agents à moins de `queue_radius` de leur sortie cible
+    fn queue_lengths(&self) -> Vec<usize> {
+        let mut queues = vec![0; self.fields.exits.len()];
+        for agent in &self.agents {
+            if let Some(field) = self.fields_for(agent.footprint).get(agent.target_exit) {
+                if field.static_distances()[agent.y][agent.x] <= self.model.queue_radius {
+                    queues[agent.target_exit] += 1;
+                }
+            }
+        }
+        queues
+    }
+
+    /// Instantané de la salle courante, réinscriptible au format ASCII
+    /// (le format ne décrit que les agents d'une cellule)
+    pub fn layout(&self) -> MapLayout {
+        let mut grid = self.grid.clone();
+        for agent in &self.agents {
+            for (x, y) in agent.cells() {
+                grid.set(x, y, CellType::Empty);
+            }
+        }
+        MapLayout {
+            grid,
+            agents: self
+                .agents
+                .iter()
+                .filter(|a| a.footprint.is_single())
+                .map(|a| (a.x, a.y))
+                .collect(),
+            spawn_cells: Vec::new(),
         }
     }
     
     pub fn step(&mut self) {
-        if self.agents.is_empty() {
+        if self.agents.is_empty() && !self.has_active_sources() {
             return;
         }
         
         self.step_count += 1;
         
+        if let Some(interval) = self.model.exit_reevaluation {
+            if interval > 0 && self.step_count.is_multiple_of(interval) {
+                self.assign_exits();
+            }
+        }
+        
         // Ordre aléatoire pour éviter les biais
-        let mut rng = rand::thread_rng();
         let mut indices: Vec<usize> = (0..self.agents.len()).collect();
-        indices.shuffle(&mut rng);
+        indices.shuffle(&mut self.rng);
         
-        let mut desired_moves: HashMap<usize, Option<(usize, usize)>> = HashMap::new();
+        let mut desired_moves: Vec<Option<(usize, usize)>> = vec![None; self.agents.len()];
+        // Agents qui ont tenté de bouger cette étape (ni pause de phase ni tour sauté)
+        let mut attempted = vec![false; self.agents.len()];
         
         let time_factor = (self.step_count as f32 * 0.1).sin();
+        let lattice = self.lattice();
         
         for &i in &indices {
             let agent = &self.agents[i];
             
-            let should_move = (time_factor + agent.phase_offset * 6.28).sin() > -0.3;
+            // Les agents encombrants se déplacent ensuite, un par un
+            if !agent.footprint.is_single() {
+                continue;
+            }
+            
+            // Chaque agent suit le champ de sa sortie cible
+            let move_fields = self.congested_fields.as_ref().unwrap_or(&self.fields);
+            let Some(floor_field) = move_fields.exits.get(agent.target_exit) else {
+                continue;
+            };
             
-            if !should_move && rng.gen::<f32>() < 0.3 { 
-                desired_moves.insert(i, None);
+            let should_move = (time_factor + agent.phase_offset * std::f32::consts::TAU).sin() > -0.3;
+            
+            if !should_move && self.rng.gen::<f32>() < 0.3 { 
                 continue;
             }
             
-            let next_pos = if self.use_probabilistic {
+            // Agents plus lents : ne tentent de bouger qu'une partie des étapes
+            if agent.move_probability < 1.0 && self.rng.gen::<f32>() >= agent.move_probability {
+                continue;
+            }
+            attempted[i] = true;
+            
+            let next_pos = if self.model.use_probabilistic {
+                let fields = FieldCoupling {
+                    static_field: floor_field.distances(),
+                    dynamic_field: self.dynamic_field.values(),
+                    k_s: self.model.k_s,
+                    k_d: self.model.k_d,
+                    k_i: self.model.k_i,
+                };
                 agent.choose_next_position_probabilistic(
-                    self.floor_field.distances(),
+                    &fields,
+                    lattice,
                     self.grid.width(),
                     self.grid.height(),
                     |x, y| self.grid.is_walkable(x, y) || (x == agent.x && y == agent.y),
-                    self.k_s,
+                    &mut self.rng,
                 )
             } else {
                 agent.choose_next_position(
-                    self.floor_field.distances(),
+                    floor_field.distances(),
+                    self.model.k_i,
+                    lattice,
                     self.grid.width(),
                     self.grid.height(),
                     |x, y| self.grid.is_walkable(x, y) || (x == agent.x && y == agent.y),
+                    &mut self.rng,
                 )
             };
             
-            desired_moves.insert(i, next_pos);
+            desired_moves[i] = next_pos;
         }
         
-        let mut target_counts: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        // Table ordonnée : le parcours (et donc l'aléa consommé) ne dépend que de la graine
+        let mut target_counts: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
         
-        for (&i, &next_pos) in &desired_moves {
-            if let Some(pos) = next_pos {
-                target_counts.entry(pos).or_insert_with(Vec::new).push(i);
+        for &i in &indices {
+            if let Some(pos) = desired_moves[i] {
+                target_counts.entry(pos).or_default().push(i);
             }
         }
         
-        let mut evacuated_indices = Vec::new();
-        let mut moved = vec![false; self.agents.len()];
+        // Un seul agent au plus entre dans chaque cellule cible
+        let mut winners = Vec::new();
+        let mut blocked_conflicts = 0;
+        for (&target, contestants) in &target_counts {
+            let winner = if contestants.len() == 1 {
+                Some(contestants[0])
+            } else {
+                self.resolve_conflict(target, contestants)
+            };
+            blocked_conflicts += contestants.len() - usize::from(winner.is_some());
+            if let Some(winner) = winner {
+                winners.push((winner, target));
+            }
+        }
         
+        let mut evacuated_indices = Vec::new();
+        let mut evacuated = vec![0; self.evacuated_per_exit.len()];
+        let mut moves = 0;
 
         for agent in &self.agents {
-            self.grid.set(agent.x, agent.y, CellType::Empty);
+            for (x, y) in agent.cells() {
+                self.grid.set(x, y, CellType::Empty);
+            }
         }
         
-        // Traiter les mouvements par ordre de priorité
-        for &i in &indices {
-            if let Some(next_pos) = desired_moves[&i] {
-                let (nx, ny) = next_pos;
-                
-                // Vérifier les conflits
-                let conflicts = target_counts.get(&(nx, ny)).map(|v| v.len()).unwrap_or(0);
-                
-                if conflicts == 1 {
-                    // Pas de conflit, mouvement garanti
-                    self.agents[i].x = nx;
-                    self.agents[i].y = ny;
-                    moved[i] = true;
-                    
-                    // Vérifier si l'agent atteint la sortie
-                    if self.grid.is_exit(nx, ny) {
-                        evacuated_indices.push(i);
-                    }
-                } else if conflicts > 1 {
-                    // Conflit : priorité au plus proche de la sortie
-                    let contestants = target_counts.get(&(nx, ny)).unwrap();
-                    let mut best_agent = i;
-                    let mut best_dist = self.floor_field.distances()[self.agents[i].y][self.agents[i].x];
-                    
-                    for &contestant in contestants {
-                        let dist = self.floor_field.distances()[self.agents[contestant].y][self.agents[contestant].x];
-                        if dist < best_dist {
-                            best_dist = dist;
-                            best_agent = contestant;
-                        }
-                    }
-                    
-                    // Seul le meilleur bouge
-                    if best_agent == i {
-                        self.agents[i].x = nx;
-                        self.agents[i].y = ny;
-                        moved[i] = true;
-                        
-                        if self.grid.is_exit(nx, ny) {
-                            evacuated_indices.push(i);
-                        }
-                    }
-                }
+        let mut moved = vec![false; self.agents.len()];
+        for (i, (nx, ny)) in winners {
+            let agent = &mut self.agents[i];
+            if (agent.x, agent.y) != (nx, ny) {
+                moves += 1;
+                moved[i] = true;
+            }
+            self.dynamic_field.deposit(agent.x, agent.y);
+            agent.move_to(nx, ny, lattice);
+            
+            // Vérifier si l'agent atteint la sortie
+            if let Some(exit) = self.exits.exit_at(nx, ny) {
+                self.evacuated_per_exit[exit] += 1;
+                evacuated[exit] += 1;
+                evacuated_indices.push(i);
+            }
+        }
+        // Attente : bloqué ou conflit perdu, pas un tour sauté par un agent lent
+        for ((agent, moved), attempted) in self.agents.iter_mut().zip(moved).zip(attempted) {
+            if attempted && !moved && agent.footprint.is_single() {
+                agent.waiting_steps += 1;
             }
         }
         
+        // Bilans dans l'ordre des identifiants, puis retrait de la salle
+        evacuated_indices.sort_by_key(|&i| self.agents[i].id);
+        for &i in &evacuated_indices {
+            let agent = &self.agents[i];
+            let exit = self.exits.exit_at(agent.x, agent.y).unwrap_or(agent.target_exit);
+            self.evacuated_agents.push(agent.record(Some((self.step_count, exit))));
+        }
         evacuated_indices.sort_by(|a, b| b.cmp(a));
         for i in evacuated_indices {
             self.agents.remove(i);
@@ -167,12 +553,324 @@ impl Simulation {
         
         // Remettre les agents sur la grille
         for agent in &self.agents {
-            self.grid.set(agent.x, agent.y, CellType::Agent);
+            for (x, y) in agent.cells() {
+                self.grid.set(x, y, CellType::Agent);
+            }
+        }
+        
+        moves += self.move_large_agents(time_factor, &mut evacuated);
+        
+        // Diffusion et décroissance des traces
+        self.dynamic_field.update(&self.grid);
+        
+        let injected = self.inject_from_sources();
+        
+        // Recalcul périodique du champ avec la foule comme surcoût
+        if let Some(interval) = self.model.congestion_interval {
+            if interval > 0 && self.step_count.is_multiple_of(interval) {
+                let exits = self
+                    .exits
+                    .exits()
+                    .iter()
+                    .map(|exit| {
+                        FloorField::with_congestion(
+                            &self.grid,
+                            &exit.cells,
+                            self.model.field_method,
+                            self.model.neighbourhood,
+                            self.model.wall_avoidance,
+                            self.model.congestion_penalty,
+                        )
+                    })
+                    .collect();
+                let footprints = self
+                    .fields
+                    .footprints
+                    .iter()
+                    .map(|&(footprint, _)| {
+                        let fields = Self::footprint_exit_fields(
+                            &self.grid,
+                            &self.exits,
+                            footprint,
+                            &self.model,
+                            self.model.congestion_penalty,
+                        );
+                        (footprint, fields)
+                    })
+                    .collect();
+                self.congested_fields = Some(FieldSet { exits, footprints });
+            }
+        }
+
+        self.last_step = StepStats {
+            step: self.step_count,
+            remaining: self.agents.len(),
+            injected,
+            evacuated,
+            moves,
+            blocked_conflicts,
+            mean_field: self.mean_field_value(),
+        };
+    }
+
+    /// Déplace un à un, dans un ordre aléatoire, les agents encombrants, après
+    /// les piétons : tout l'encombrement doit tenir sur des cellules libres ou
+    /// déjà occupées par l'agent. Retourne le nombre de déplacements.
+    fn move_large_agents(&mut self, time_factor: f32, evacuated: &mut [usize]) -> usize {
+        let mut indices: Vec<usize> = (0..self.agents.len())
+            .filter(|&i| !self.agents[i].footprint.is_single())
+            .collect();
+        if indices.is_empty() {
+            return 0;
+        }
+        indices.shuffle(&mut self.rng);
+
+        let lattice = self.lattice();
+        let mut moves = 0;
+        let mut evacuated_indices = Vec::new();
+        for i in indices {
+            let agent = self.agents[i];
+            let fields = self.congested_fields.as_ref().unwrap_or(&self.fields).for_footprint(agent.footprint);
+            let Some(floor_field) = fields.get(agent.target_exit) else {
+                self.agents[i].waiting_steps += 1;
+                continue;
+            };
+
+            let should_move = (time_factor + agent.phase_offset * std::f32::consts::TAU).sin() > -0.3;
+            let skip = (!should_move && self.rng.gen::<f32>() < 0.3)
+                || (agent.move_probability < 1.0 && self.rng.gen::<f32>() >= agent.move_probability);
+
+            let own: Vec<(usize, usize)> = agent.cells().collect();
+            let is_walkable = |x: usize, y: usize| self.grid.fits(agent.footprint, x, y, &own);
+            let next_pos = if skip {
+                None
+            } else if self.model.use_probabilistic {
+                let coupling = FieldCoupling {
+                    static_field: floor_field.distances(),
+                    dynamic_field: self.dynamic_field.values(),
+                    k_s: self.model.k_s,
+                    k_d: self.model.k_d,
+                    k_i: self.model.k_i,
+                };
+                agent.choose_next_position_probabilistic(
+                    &coupling,
+                    lattice,
+                    self.grid.width(),
+                    self.grid.height(),
+                    is_walkable,
+                    &mut self.rng,
+                )
+            } else {
+                agent.choose_next_position(
+                    floor_field.distances(),
+                    self.model.k_i,
+                    lattice,
+                    self.grid.width(),
+                    self.grid.height(),
+                    is_walkable,
+                    &mut self.rng,
+                )
+            };
+
+            let Some((nx, ny)) = next_pos.filter(|&pos| pos != (agent.x, agent.y)) else {
+                if !skip {
+                    self.agents[i].waiting_steps += 1;
+                }
+                continue;
+            };
+            for &(x, y) in &own {
+                self.grid.set(x, y, CellType::Empty);
+            }
+            self.dynamic_field.deposit(agent.x, agent.y);
+            self.agents[i].move_to(nx, ny, lattice);
+            moves += 1;
+
+            // Sorti dès que son encombrement recouvre une cellule de sortie
+            let agent = self.agents[i];
+            if let Some(exit) = agent.cells().find_map(|(x, y)| self.exits.exit_at(x, y)) {
+                self.evacuated_per_exit[exit] += 1;
+                evacuated[exit] += 1;
+                evacuated_indices.push((i, exit));
+            } else {
+                for (x, y) in agent.cells() {
+                    self.grid.set(x, y, CellType::Agent);
+                }
+            }
+        }
+
+        evacuated_indices.sort_by_key(|&(i, _)| self.agents[i].id);
+        for &(i, exit) in &evacuated_indices {
+            self.evacuated_agents.push(self.agents[i].record(Some((self.step_count, exit))));
+        }
+        evacuated_indices.sort_by_key(|&(i, _)| std::cmp::Reverse(i));
+        for (i, _) in evacuated_indices {
+            self.agents.remove(i);
+        }
+        moves
+    }
+
+    /// Place les agents dus par chaque source sur des cellules libres de sa
+    /// zone, tirées au hasard ; faute de place, ils attendent l'étape suivante
+    fn inject_from_sources(&mut self) -> usize {
+        let mut injected = 0;
+        for s in 0..self.sources.len() {
+            let due = self.sources[s].due(self.step_count);
+            if due == 0 {
+                continue;
+            }
+            let mut free: Vec<(usize, usize)> = self.sources[s]
+                .cells()
+                .iter()
+                .copied()
+                .filter(|&(x, y)| self.grid.is_empty(x, y))
+                .collect();
+            free.shuffle(&mut self.rng);
+            free.truncate(due);
+
+            let mut queues = self.queue_lengths();
+            for &(x, y) in &free {
+                let mut agent = Agent::new(x, y, self.next_agent_id, &mut self.rng);
+                agent.spawn_step = self.step_count;
+                if let Some(exit) = self.exits.choose(
+                    self.model.exit_strategy,
+                    &self.fields.exits,
+                    &queues,
+                    self.model.exit_flow,
+                    (x, y),
+                ) {
+                    agent.target_exit = exit;
+                    if self.fields.exits[exit].static_distances()[y][x] <= self.model.queue_radius {
+                        queues[exit] += 1;
+                    }
+                }
+                self.next_agent_id += 1;
+                self.grid.set(x, y, CellType::Agent);
+                self.agents.push(agent);
+                self.assign_profile(self.agents.len() - 1);
+            }
+            self.sources[s].placed(free.len());
+            injected += free.len();
+        }
+        self.injected_count += injected;
+        injected
+    }
+
+    /// Tire le profil de l'agent `i` dans le mélange de la population
+    /// (aucun tirage si le mélange est vide : l'agent reste adulte)
+    fn assign_profile(&mut self, i: usize) {
+        if let Some(share) = draw_profile(&self.profiles, &mut self.rng) {
+            let agent = &mut self.agents[i];
+            agent.profile = share.profile;
+            agent.move_probability = move_probability(share.speed(), self.model.free_speed);
+        }
+    }
+
+    /// Vrai si une source peut encore ajouter des agents
+    fn has_active_sources(&self) -> bool {
+        self.sources.iter().any(|source| source.is_active(self.step_count))
+    }
+
+    /// Moyenne du champ statique de la sortie cible sous chaque agent
+    /// (cellules hors d'atteinte ignorées)
+    fn mean_field_value(&self) -> f32 {
+        let values: Vec<f32> = (0..self.agents.len())
+            .map(|i| self.distance_to_target(i))
+            .filter(|d| d.is_finite())
+            .collect();
+        if values.is_empty() {
+            0.0
+        } else {
+            values.iter().sum::<f32>() / values.len() as f32
         }
     }
     
-    pub fn draw(&self, cell_size: f32) {
-        self.grid.draw(cell_size);
+    /// Désigne l'agent qui entre dans `target` parmi plusieurs prétendants,
+    /// ou aucun (friction)
+    fn resolve_conflict(&mut self, target: (usize, usize), contestants: &[usize]) -> Option<usize> {
+        match self.model.conflict_policy {
+            ConflictPolicy::NearestWins => {
+                // Priorité au plus proche de sa sortie (le premier en cas d'égalité)
+                contestants
+                    .iter()
+                    .copied()
+                    .min_by(|&a, &b| self.distance_to_target(a).total_cmp(&self.distance_to_target(b)))
+            }
+            ConflictPolicy::UniformRandom => contestants.choose(&mut self.rng).copied(),
+            ConflictPolicy::ProbabilityWeighted => self.weighted_winner(target, contestants),
+            ConflictPolicy::Friction => {
+                // Avec la probabilité mu, personne ne bouge
+                if self.rng.gen::<f32>() < self.model.friction {
+                    None
+                } else {
+                    self.weighted_winner(target, contestants)
+                }
+            }
+        }
+    }
+
+    /// Tirage du gagnant pondéré par la préférence de chaque agent pour la
+    /// cible : exp(k_s * gain de champ) pour se déplacer vers `target`
+    fn weighted_winner(&mut self, (tx, ty): (usize, usize), contestants: &[usize]) -> Option<usize> {
+        let scores: Vec<f32> = contestants
+            .iter()
+            .map(|&i| {
+                let agent = &self.agents[i];
+                let move_fields = self.congested_fields.as_ref().unwrap_or(&self.fields);
+                match move_fields.for_footprint(agent.footprint).get(agent.target_exit) {
+                    Some(field) => {
+                        let distances = field.distances();
+                        self.model.k_s * (distances[agent.y][agent.x] - distances[ty][tx])
+                    }
+                    None => f32::NEG_INFINITY,
+                }
+            })
+            .collect();
+
+        let best = scores.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        if !best.is_finite() {
+            return contestants.choose(&mut self.rng).copied();
+        }
+        let weights: Vec<f32> = scores.iter().map(|s| (s - best).exp()).collect();
+        let mut roll = self.rng.gen::<f32>() * weights.iter().sum::<f32>();
+
+        for (&i, &weight) in contestants.iter().zip(weights.iter()) {
+            roll -= weight;
+            if roll <= 0.0 {
+                return Some(i);
+            }
+        }
+        contestants.last().copied()
+    }
+
+    /// Distance de l'agent `i` à sa sortie cible
+    fn distance_to_target(&self, i: usize) -> f32 {
+        let agent = &self.agents[i];
+        self.fields_for(agent.footprint)
+            .get(agent.target_exit)
+            .map(|field| field.static_distances()[agent.y][agent.x])
+            .unwrap_or(f32::INFINITY)
+    }
+    
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Voisinage du modèle sur le pavage de la grille
+    pub fn lattice(&self) -> Lattice {
+        Lattice::new(self.grid.topology(), self.model.neighbourhood)
+    }
+    
+    pub fn agents(&self) -> &[Agent] {
+        &self.agents
+    }
+    
+    /// Champs statiques, un par sortie (même ordre que `exits()`)
+    pub fn exit_fields(&self) -> &[FloorField] {
+        &self.fields.exits
+    }
+    
+    pub fn dynamic_field(&self) -> &DynamicFloorField {
+        &self.dynamic_field
     }
     
     pub fn agent_count(&self) -> usize {
@@ -182,4 +880,145 @@ impl Simulation {
     pub fn step_count(&self) -> usize {
         self.step_count
     }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    
+    pub fn exits(&self) -> &[Exit] {
+        self.exits.exits()
+    }
+    
+    /// Nombre d'agents sortis par chaque sortie
+    pub fn evacuated_per_exit(&self) -> &[usize] {
+        &self.evacuated_per_exit
+    }
+
+    /// Bilans des agents sortis, dans l'ordre de sortie
+    pub fn evacuated_agents(&self) -> &[AgentRecord] {
+        &self.evacuated_agents
+    }
+
+    /// Bilans de tous les agents (sortis puis restants), triés par identifiant
+    pub fn agent_records(&self) -> Vec<AgentRecord> {
+        let mut records: Vec<AgentRecord> = self
+            .evacuated_agents
+            .iter()
+            .cloned()
+            .chain(self.agents.iter().map(|agent| agent.record(None)))
+            .collect();
+        records.sort_by_key(|record| record.id);
+        records
+    }
+
+    /// Mesures de la dernière étape, à enregistrer avec un `Recorder`
+    pub fn last_step(&self) -> &StepStats {
+        &self.last_step
+    }
+
+    pub fn summary(&self) -> RunSummary {
+        let evacuated = self.evacuated_per_exit.iter().sum();
+        RunSummary {
+            seed: self.seed,
+            steps: self.step_count,
+            initial_agents: self.initial_agent_count,
+            injected_agents: self.injected_count,
+            evacuated,
+            remaining: self.agents.len(),
+            agents_per_step: if self.step_count > 0 {
+                evacuated as f32 / self.step_count as f32
+            } else {
+                0.0
+            },
+            completed: self.agents.is_empty() && !self.has_active_sources(),
+            exits: self
+                .exits()
+                .iter()
+                .zip(&self.evacuated_per_exit)
+                .map(|(exit, &evacuated)| ExitSummary { name: exit.name.clone(), evacuated })
+                .collect(),
+            profiles: self.profile_summaries(),
+        }
+    }
+
+    /// Statistiques par profil, pour les profils présents
+    pub fn profile_summaries(&self) -> Vec<ProfileSummary> {
+        let records = self.agent_records();
+        let mut profiles: Vec<AgentProfile> = records.iter().map(|record| record.profile).collect();
+        profiles.sort();
+        profiles.dedup();
+
+        profiles
+            .into_iter()
+            .map(|profile| {
+                let group: Vec<_> = records.iter().filter(|record| record.profile == profile).collect();
+                let times: Vec<f32> = group
+                    .iter()
+                    .filter_map(|record| record.exit_step.map(|step| (step - record.spawn_step) as f32))
+                    .collect();
+                let count = group.len() as f32;
+                ProfileSummary {
+                    profile,
+                    agents: group.len(),
+                    evacuated: times.len(),
+                    mean_evacuation_time: (!times.is_empty())
+                        .then(|| times.iter().sum::<f32>() / times.len() as f32),
+                    mean_waiting_steps: group.iter().map(|record| record.waiting_steps as f32).sum::<f32>() / count,
+                    mean_path_length: group.iter().map(|record| record.path_length).sum::<f32>() / count,
+                }
+            })
+            .collect()
+    }
+    
+    /// Avance jusqu'à une condition d'arrêt et retourne le bilan
+    pub fn run(&mut self) -> RunSummary {
+        while !self.is_finished() {
+            self.step();
+        }
+        self.summary()
+    }
+    
+    /// Vrai quand l'évacuation est terminée (sources taries comprises), que
+    /// les agents restants ne peuvent plus atteindre aucune sortie ou qu'une
+    /// condition d'arrêt est atteinte
+    pub fn is_finished(&self) -> bool {
+        let done = self.agents.len() <= self.stop.max_remaining || !self.can_reach_exit();
+        (done && !self.has_active_sources())
+            || self.stop.max_steps.is_some_and(|max| self.step_count >= max)
+    }
+
+    /// Vrai si au moins un agent a une distance finie à l'une des sorties
+    fn can_reach_exit(&self) -> bool {
+        self.agents.iter().any(|agent| {
+            self.fields
+                .for_footprint(agent.footprint)
+                .iter()
+                .any(|field| field.static_distances()[agent.y][agent.x].is_finite())
+        })
+    }
+
+    pub fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+}
+
+/// Champs par sortie des piétons et de chaque encombrement d'agent à plusieurs cellules
+struct FieldSet {
+    /// Un champ par sortie, indexé par l'identifiant de la sortie
+    exits: Vec<FloorField>,
+    footprints: Vec<(Footprint, Vec<FloorField>)>,
+}
+
+impl FieldSet {
+    /// Champs d'un encombrement : ceux des sorties pour un piéton d'une cellule
+    fn for_footprint(&self, footprint: Footprint) -> &[FloorField] {
+        if footprint.is_single() {
+            return &self.exits;
+        }
+        self.footprints
+            .iter()
+            .find(|(f, _)| *f == footprint)
+            .map(|(_, fields)| fields.as_slice())
+            .unwrap_or(&self.exits)
+    }
 }
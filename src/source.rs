@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use crate::grid::{CellType, Grid};
+
+/// Source d'agents : une zone rectangulaire (bornes incluses) où des agents
+/// apparaissent au fil du temps, par exemple 2 agents/étape à l'entrée d'un
+/// couloir pendant 100 étapes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SourceConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub min: [usize; 2],
+    pub max: [usize; 2],
+    /// Agents par étape ; un débit fractionnaire s'accumule d'une étape à l'autre
+    pub rate: f32,
+    /// Première étape d'injection
+    #[serde(default)]
+    pub start: usize,
+    /// Dernière étape d'injection (illimitée si absente)
+    #[serde(default)]
+    pub end: Option<usize>,
+    /// Nombre maximal d'agents injectés (illimité si absent)
+    #[serde(default)]
+    pub total: Option<usize>,
+}
+
+/// État d'une source pendant la simulation
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub config: SourceConfig,
+    /// Cellules praticables de la zone
+    cells: Vec<(usize, usize)>,
+    /// Agents dus mais pas encore placés (fraction de débit ou zone pleine)
+    pending: f32,
+    injected: usize,
+}
+
+impl Source {
+    pub fn new(config: SourceConfig, grid: &Grid) -> Self {
+        let cells = (config.min[1]..=config.max[1])
+            .flat_map(|y| (config.min[0]..=config.max[0]).map(move |x| (x, y)))
+            .filter(|&(x, y)| matches!(grid.get(x, y), Some(cell) if cell != CellType::Wall && cell != CellType::Exit))
+            .collect();
+        Source { config, cells, pending: 0.0, injected: 0 }
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    pub fn injected(&self) -> usize {
+        self.injected
+    }
+
+    fn remaining_total(&self) -> usize {
+        self.config.total.map_or(usize::MAX, |total| total.saturating_sub(self.injected))
+    }
+
+    /// Vrai tant que la source peut encore injecter des agents à partir de `step`
+    pub fn is_active(&self, step: usize) -> bool {
+        if self.cells.is_empty() || self.config.rate <= 0.0 || self.remaining_total() == 0 {
+            return false;
+        }
+        // Les agents dus en fin de plage attendent encore une place
+        self.config.end.is_none_or(|end| step < end || self.pending >= 1.0)
+    }
+
+    /// Crédite le débit de l'étape et retourne le nombre d'agents à placer
+    pub fn due(&mut self, step: usize) -> usize {
+        let in_range = step >= self.config.start && self.config.end.is_none_or(|end| step <= end);
+        if in_range {
+            self.pending += self.config.rate;
+        }
+        (self.pending.floor() as usize).min(self.remaining_total())
+    }
+
+    /// Enregistre `count` agents effectivement placés
+    pub fn placed(&mut self, count: usize) {
+        self.injected += count;
+        self.pending -= count as f32;
+        if self.remaining_total() == 0 {
+            self.pending = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(rate: f32, start: usize, end: Option<usize>, total: Option<usize>) -> Source {
+        let grid = Grid::from_cells(vec![vec![CellType::Empty; 3]; 3]);
+        let config = SourceConfig { name: None, min: [0, 0], max: [2, 2], rate, start, end, total };
+        Source::new(config, &grid)
+    }
+
+    /// Agents placés à chaque étape de 0 à `steps` (toutes les places sont libres)
+    fn schedule(source: &mut Source, steps: usize) -> Vec<usize> {
+        (0..=steps)
+            .map(|step| {
+                let due = source.due(step);
+                source.placed(due);
+                due
+            })
+            .collect()
+    }
+
+    #[test]
+    fn end_step_is_inclusive() {
+        let mut source = source(1.0, 2, Some(4), None);
+        assert_eq!(schedule(&mut source, 6), vec![0, 0, 1, 1, 1, 0, 0]);
+        assert_eq!(source.injected(), 3);
+    }
+
+    #[test]
+    fn fractional_rate_accumulates() {
+        let mut source = source(0.5, 0, Some(3), None);
+        assert_eq!(schedule(&mut source, 4), vec![0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn total_caps_injections() {
+        let mut source = source(2.0, 0, None, Some(3));
+        assert_eq!(schedule(&mut source, 3), vec![2, 1, 0, 0]);
+        assert!(!source.is_active(4));
+    }
+
+    #[test]
+    fn active_until_end_or_pending_agents_are_placed() {
+        let mut source = source(1.0, 0, Some(2), None);
+        assert!(source.is_active(1));
+        assert!(!source.is_active(2));
+        // Zone pleine : l'agent dû attend une place après la fin de plage
+        source.due(2);
+        assert!(source.is_active(3));
+        source.placed(1);
+        assert!(!source.is_active(3));
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::agent::Footprint;
+use crate::grid::{CellType, Grid, Topology};
+use crate::neighbourhood::{Lattice, Neighbourhood};
+use crate::profile::AgentProfile;
+
+/// Zone d'apparition nommée : rectangle ou polygone, avec un nombre
+/// d'agents (`count`) ou une densité en personnes/m² (`density`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnZone {
+    pub name: String,
+    #[serde(flatten)]
+    pub area: ZoneArea,
+    #[serde(default)]
+    pub count: Option<usize>,
+    #[serde(default)]
+    pub density: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ZoneArea {
+    /// Rectangle de cellules, bornes incluses
+    Rect { min: [usize; 2], max: [usize; 2] },
+    /// Polygone en coordonnées de grille continues : une cellule en fait
+    /// partie si son centre (x + 0.5, y + 0.5 sur une grille carrée) est à
+    /// l'intérieur
+    Polygon { polygon: Vec<[f32; 2]> },
+}
+
+impl ZoneArea {
+    pub fn contains(&self, x: usize, y: usize, topology: Topology) -> bool {
+        match self {
+            ZoneArea::Rect { min, max } => {
+                (min[0]..=max[0]).contains(&x) && (min[1]..=max[1]).contains(&y)
+            }
+            ZoneArea::Polygon { polygon } => {
+                // Règle pair-impair sur une demi-droite horizontale
+                let (px, py) = Lattice::new(topology, Neighbourhood::Moore).centre(x, y);
+                let mut inside = false;
+                for (i, a) in polygon.iter().enumerate() {
+                    let b = polygon[(i + 1) % polygon.len()];
+                    if (a[1] > py) != (b[1] > py) {
+                        let cross_x = a[0] + (py - a[1]) / (b[1] - a[1]) * (b[0] - a[0]);
+                        if px < cross_x {
+                            inside = !inside;
+                        }
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    /// Cellules de la zone où un agent peut apparaître (ni mur ni sortie)
+    pub fn cells(&self, grid: &Grid) -> Vec<(usize, usize)> {
+        (0..grid.height())
+            .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.contains(x, y, grid.topology()))
+            .filter(|&(x, y)| matches!(grid.get(x, y), Some(CellType::Empty) | Some(CellType::Agent)))
+            .collect()
+    }
+}
+
+impl SpawnZone {
+    /// Cellules candidates et nombre d'agents à y placer ; `cell_size` (m,
+    /// largeur d'une cellule) convertit la densité en nombre d'agents
+    pub fn group(&self, grid: &Grid, cell_size: f32) -> Result<SpawnGroup, PlacementError> {
+        let cells = self.area.cells(grid);
+        let count = match (self.count, self.density) {
+            (Some(count), None) => count,
+            (None, Some(density)) => {
+                let cell_area = Lattice::new(grid.topology(), Neighbourhood::Moore).cell_area();
+                (density * cells.len() as f32 * cell_size * cell_size * cell_area).round() as usize
+            }
+            _ => return Err(PlacementError::CountOrDensity { zone: self.name.clone() }),
+        };
+        Ok(SpawnGroup::new(self.name.clone(), cells, count))
+    }
+}
+
+/// Groupe d'agents encombrants (fauteuils roulants, poussettes, lits)
+/// occupant plusieurs cellules (`[[population.large_agents]]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LargeAgentGroup {
+    pub name: String,
+    /// Largeur et hauteur en cellules : [2, 1] pour un lit couché
+    /// horizontalement, [1, 2] verticalement, [2, 2] pour un fauteuil
+    pub footprint: [usize; 2],
+    pub count: usize,
+    /// Zone où tirer les agents (toute la salle si absente) ; la cellule en
+    /// haut à gauche de l'encombrement doit s'y trouver
+    #[serde(default)]
+    pub zone: Option<ZoneArea>,
+    #[serde(default = "default_large_profile")]
+    pub profile: AgentProfile,
+    /// Vitesse souhaitée en m/s (valeur par défaut du profil si absente)
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+fn default_large_profile() -> AgentProfile {
+    AgentProfile::Impaired
+}
+
+impl LargeAgentGroup {
+    pub fn group(&self, grid: &Grid) -> SpawnGroup {
+        let cells = match &self.zone {
+            Some(zone) => zone.cells(grid),
+            None => (0..grid.height())
+                .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+                .collect(),
+        };
+        SpawnGroup {
+            footprint: Footprint { width: self.footprint[0].max(1), height: self.footprint[1].max(1) },
+            profile: Some((self.profile, self.speed.unwrap_or_else(|| self.profile.default_speed()))),
+            ..SpawnGroup::new(self.name.clone(), cells, self.count)
+        }
+    }
+}
+
+/// Agents à tirer au hasard, sans remise, parmi des cellules candidates
+#[derive(Debug, Clone)]
+pub struct SpawnGroup {
+    pub name: String,
+    /// Positions candidates (cellule en haut à gauche pour un agent encombrant)
+    pub cells: Vec<(usize, usize)>,
+    pub count: usize,
+    pub footprint: Footprint,
+    /// Profil et vitesse (m/s) imposés au groupe, sinon tirés dans le mélange
+    pub profile: Option<(AgentProfile, f32)>,
+}
+
+impl SpawnGroup {
+    /// Groupe de piétons d'une cellule, au profil tiré dans le mélange
+    pub fn new(name: String, cells: Vec<(usize, usize)>, count: usize) -> Self {
+        SpawnGroup { name, cells, count, footprint: Footprint::SINGLE, profile: None }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlacementError {
+    /// La zone n'a pas assez de cellules libres
+    ZoneFull { zone: String, requested: usize, available: usize },
+    /// Position explicite hors de la grille, sur un mur, une sortie ou un autre agent
+    InvalidCell { x: usize, y: usize },
+    /// Une zone doit donner exactement l'un de `count` et `density`
+    CountOrDensity { zone: String },
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::ZoneFull { zone, requested, available } => write!(
+                f,
+                "la zone « {} » ne peut accueillir que {} agents ({} demandés)",
+                zone, available, requested
+            ),
+            PlacementError::InvalidCell { x, y } => {
+                write!(f, "impossible de placer un agent en ({}, {}) : cellule non libre", x, y)
+            }
+            PlacementError::CountOrDensity { zone } => {
+                write!(f, "la zone « {} » doit définir soit count soit density", zone)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
@@ -0,0 +1,178 @@
+use std::io::{self, Write};
+use crate::batch::run_replications;
+use crate::grid::{ObstaclePattern, Topology};
+use crate::neighbourhood::Neighbourhood;
+use crate::scenario::{ConflictPolicy, GridSource, Scenario, ScenarioError};
+use crate::simulation::RunSummary;
+
+/// Valeurs à balayer pour chaque paramètre. Une liste vide garde la valeur
+/// du scénario de base.
+#[derive(Debug, Clone, Default)]
+pub struct SweepSpec {
+    pub agents: Vec<usize>,
+    pub k_s: Vec<f32>,
+    /// Balayer la friction impose la politique de conflit `friction`
+    pub friction: Vec<f32>,
+    pub patterns: Vec<ObstaclePattern>,
+    pub probabilistic: Vec<bool>,
+    pub neighbourhoods: Vec<Neighbourhood>,
+    pub topologies: Vec<Topology>,
+}
+
+/// Un point du produit cartésien
+#[derive(Debug, Clone, Copy)]
+pub struct SweepPoint {
+    pub pattern: Option<ObstaclePattern>,
+    pub agents: usize,
+    pub k_s: f32,
+    pub friction: f32,
+    pub probabilistic: bool,
+    pub neighbourhood: Neighbourhood,
+    pub topology: Topology,
+}
+
+/// Une ligne du CSV : un point, une réplication
+#[derive(Debug, Clone)]
+pub struct SweepRow {
+    pub point: SweepPoint,
+    pub replication: usize,
+    pub summary: RunSummary,
+}
+
+impl SweepSpec {
+    /// Produit cartésien des listes, complétées par les valeurs du scénario de base
+    pub fn points(&self, base: &Scenario) -> Vec<SweepPoint> {
+        let base_pattern = match base.grid {
+            GridSource::Pattern { pattern, .. } => Some(pattern),
+            _ => None,
+        };
+        let patterns: Vec<Option<ObstaclePattern>> = if self.patterns.is_empty() {
+            vec![base_pattern]
+        } else {
+            self.patterns.iter().copied().map(Some).collect()
+        };
+        let agents = or_base(&self.agents, base.population.agents);
+        let k_s = or_base(&self.k_s, base.model.k_s);
+        let friction = or_base(&self.friction, base.model.friction);
+        let probabilistic = or_base(&self.probabilistic, base.model.use_probabilistic);
+        let neighbourhoods = or_base(&self.neighbourhoods, base.model.neighbourhood);
+        let topologies = or_base(&self.topologies, base.topology);
+
+        let mut points = Vec::new();
+        for &topology in &topologies {
+            for &pattern in &patterns {
+                for &agents in &agents {
+                    for &k_s in &k_s {
+                        for &friction in &friction {
+                            for &probabilistic in &probabilistic {
+                                for &neighbourhood in &neighbourhoods {
+                                    points.push(SweepPoint {
+                                        pattern,
+                                        agents,
+                                        k_s,
+                                        friction,
+                                        probabilistic,
+                                        neighbourhood,
+                                        topology,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        points
+    }
+
+    /// Scénario de base modifié pour le point `point`
+    pub fn apply(&self, base: &Scenario, point: &SweepPoint) -> Scenario {
+        let mut scenario = base.clone();
+        if let Some(pattern) = point.pattern {
+            let (width, height) = match scenario.grid {
+                GridSource::Pattern { width, height, .. } => (width, height),
+                _ => (60, 40),
+            };
+            scenario.grid = GridSource::Pattern { width, height, pattern };
+        }
+        scenario.population.agents = point.agents;
+        scenario.model.k_s = point.k_s;
+        scenario.model.friction = point.friction;
+        scenario.model.use_probabilistic = point.probabilistic;
+        scenario.model.neighbourhood = point.neighbourhood;
+        scenario.topology = point.topology;
+        if !self.friction.is_empty() {
+            scenario.model.conflict_policy = ConflictPolicy::Friction;
+        }
+        scenario
+    }
+}
+
+fn or_base<T: Copy>(values: &[T], base: T) -> Vec<T> {
+    if values.is_empty() {
+        vec![base]
+    } else {
+        values.to_vec()
+    }
+}
+
+/// Exécute `replications` simulations par point. Tous les points utilisent
+/// les mêmes graines `base_seed + i` pour que les courbes soient comparables.
+pub fn run_sweep(
+    base: &Scenario,
+    spec: &SweepSpec,
+    replications: usize,
+    threads: usize,
+    base_seed: u64,
+) -> Result<Vec<SweepRow>, ScenarioError> {
+    let mut rows = Vec::new();
+    for point in spec.points(base) {
+        let scenario = spec.apply(base, &point);
+        let runs = run_replications(&scenario, replications, threads, base_seed)?;
+        rows.extend(runs.into_iter().enumerate().map(|(replication, summary)| SweepRow {
+            point,
+            replication,
+            summary,
+        }));
+    }
+    Ok(rows)
+}
+
+/// CSV « tidy » : une ligne par simulation, une colonne par variable
+pub fn write_csv(rows: &[SweepRow], mut out: impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "topology,pattern,agents,k_s,friction,probabilistic,neighbourhood,replication,seed,steps,evacuated,remaining,completed,agents_per_step"
+    )?;
+    for row in rows {
+        let pattern = row.point.pattern.map(enum_name).unwrap_or_default();
+        let s = &row.summary;
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{:.4}",
+            enum_name(row.point.topology),
+            pattern,
+            row.point.agents,
+            row.point.k_s,
+            row.point.friction,
+            row.point.probabilistic,
+            row.point.neighbourhood,
+            row.replication,
+            s.seed,
+            s.steps,
+            s.evacuated,
+            s.remaining,
+            s.completed,
+            s.agents_per_step
+        )?;
+    }
+    Ok(())
+}
+
+/// Nom tel qu'écrit dans les scénarios (`two_exits_far`, `hexagonal`...)
+fn enum_name(value: impl serde::Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default()
+}